@@ -0,0 +1,601 @@
+//! A basic theorem prover for path semantics.
+//!
+//! Boolean functions are written as `if(a, b)`,
+//! where `a` is the value for `true` and `b` is the value for `false`.
+//! Facts are derived by `infer` using `monotonic_solver`.
+
+extern crate boolean_paths;
+extern crate math_notation;
+extern crate monotonic_solver;
+
+pub use proof::{prove, prove_with, Proof, Step};
+pub use refute::{prove_or_refute, Counterexample, Outcome};
+pub use rules::{Rule, Rules};
+pub use syntax::{parse, parse_lines};
+
+pub mod notation;
+pub mod proof;
+pub mod refute;
+pub mod rules;
+pub mod sat;
+mod syntax;
+
+use std::collections::HashSet;
+use std::fmt::{self, Display, Formatter};
+use std::sync::Arc;
+
+use Expression::*;
+
+/// An expression of the theorem prover.
+#[derive(Clone, Hash, PartialEq, Eq, Debug)]
+pub enum Expression {
+    /// `false`.
+    False,
+    /// `true`.
+    True,
+    /// A function `if(a, b)` returning `a` for `true` and `b` for `false`.
+    ///
+    /// This is also a tuple `(a, b)`, see `tuple`.
+    If(Box<Expression>, Box<Expression>),
+    /// A path `[f] a`.
+    Path(Box<Expression>, Box<Expression>),
+    /// A variable.
+    Variable(Arc<String>),
+    /// A type judgement `a : b`.
+    IsType(Box<Expression>, Box<Expression>),
+    /// A definition `a := b`.
+    Define(Box<Expression>, Box<Expression>),
+    /// A function application `f(a)`.
+    Apply(Box<Expression>, Box<Expression>),
+    /// The result of checking an expression, `check `a` is `b``.
+    Check(Box<Expression>, Box<Expression>),
+    /// An equality `a == b`.
+    Eq(Box<Expression>, Box<Expression>),
+    /// An existential path `(∃f)`.
+    Ex(Box<Expression>),
+}
+
+impl Display for Expression {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        // Parentheses are added where the printed syntax would be read differently.
+        fn term(a: &Expression) -> Paren<'_> {Paren(a, is_binary(a))}
+        fn atom(a: &Expression) -> Paren<'_> {Paren(a, is_binary(a) || matches!(*a, Path(_, _)))}
+        match *self {
+            False => write!(fmt, "false")?,
+            True => write!(fmt, "true")?,
+            If(ref a, ref b) => write!(fmt, "if({}, {})", a, b)?,
+            Path(ref a, ref b) => write!(fmt, "[{}] {}", a, term(b))?,
+            Variable(ref a) => write!(fmt, "{}", a)?,
+            IsType(ref a, ref b) => write!(fmt, "{} : {}", term(a), term(b))?,
+            Define(ref a, ref b) => write!(fmt, "{} := {}", term(a), term(b))?,
+            Apply(ref a, ref b) => write!(fmt, "{}({})", atom(a), b)?,
+            Check(ref a, ref b) => write!(fmt, "check `{}` is `{}`", a, b)?,
+            Eq(ref a, ref b) => write!(fmt, "{} == {}", term(a), term(b))?,
+            Ex(ref a) => write!(fmt, "(∃{})", a)?,
+        }
+        Ok(())
+    }
+}
+
+// Displays an expression, in parentheses if the flag is set.
+struct Paren<'a>(&'a Expression, bool);
+
+impl<'a> Display for Paren<'a> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        if self.1 {write!(fmt, "({})", self.0)} else {write!(fmt, "{}", self.0)}
+    }
+}
+
+// Returns `true` for `a : b`, `a := b` and `a == b`, which have the lowest precedence.
+fn is_binary(expr: &Expression) -> bool {
+    matches!(*expr, IsType(_, _) | Define(_, _) | Eq(_, _))
+}
+
+/// Infers a new fact from the story.
+///
+/// This is used as the inference rule of `monotonic_solver`.
+pub fn infer(
+    cache: &HashSet<Expression>,
+    filter_cache: &HashSet<Expression>,
+    story: &[Expression]
+) -> Option<Expression> {
+    proof::infer_step(&Rules::standard(), cache, filter_cache, story).map(|step| step.fact)
+}
+
+/// Evaluates an expression using the definitions in `exprs`.
+///
+/// Expressions that can not be reduced further, e.g. undefined variables,
+/// are kept as they are, such that `eval` returns a normal form.
+/// Returns an error for stuck terms, e.g. applying `true` to an argument.
+pub fn eval(expr: &Expression, exprs: &[Expression]) -> Result<Box<Expression>, String> {
+    match *expr {
+        False => Ok(false_()),
+        True => Ok(true_()),
+        Apply(ref f, ref v) => {
+            if let If(ref t, ref f) = **f {
+                let new_v = expect_value(eval(v, exprs)?)?;
+                return match *new_v {
+                    False => eval(f, exprs),
+                    True => eval(t, exprs),
+                    // Applying to a tuple uses the first element as the first argument
+                    // and the tail as arguments for the rest.
+                    If(ref vt, ref vf) => {
+                        eval(&apply(apply(if_(t.clone(), f.clone()), vt.clone()), vf.clone()),
+                             exprs)
+                    }
+                    _ => Ok(apply(if_(t.clone(), f.clone()), new_v.clone())),
+                };
+            }
+            if let Ex(ref g) = **f {
+                if let If(ref t, ref f) = *eval(g, exprs)? {
+                    let new_t = eval(t, exprs)?;
+                    let new_f = eval(f, exprs)?;
+                    let new_v = expect_value(eval(v, exprs)?)?;
+                    return if new_v == new_t || new_v == new_f ||
+                       *eval(&apply(ex(new_t), new_v.clone()), exprs)? == True ||
+                       *eval(&apply(ex(new_f), new_v), exprs)? == True {
+                        Ok(true_())
+                    } else {
+                        Ok(false_())
+                    };
+                }
+            }
+            let new_f = eval(f, exprs)?;
+            let new_v = expect_value(eval(v, exprs)?)?;
+            match *new_f {
+                False | True | Path(_, _) | IsType(_, _) | Define(_, _) | Check(_, _) | Eq(_, _) => {
+                    Err(format!("Can not apply `{}` to `{}`", new_f, new_v))
+                }
+                _ if f != &new_f || v != &new_v => eval(&apply(new_f, new_v), exprs),
+                _ => Ok(Box::new(expr.clone())),
+            }
+        }
+        Variable(ref a) => {
+            match definition(a, exprs) {
+                // Self-referential definitions, e.g. `a := not(a)`, are not expanded.
+                Some(val) if !refers_to(val, a, exprs, &mut vec![]) => eval(val, exprs),
+                _ => Ok(Box::new(expr.clone())),
+            }
+        }
+        If(ref t, ref f) => {
+            let new_t = expect_value(eval(t, exprs)?)?;
+            let new_f = expect_value(eval(f, exprs)?)?;
+            Ok(if_(new_t, new_f))
+        }
+        Path(ref f, ref v) => {
+            let new_f = expect_value(eval(f, exprs)?)?;
+            let new_v = expect_value(eval(v, exprs)?)?;
+            Ok(path(new_f, new_v))
+        }
+        IsType(ref a, ref ty) => {
+            match *eval(ty, exprs)? {
+                // `a : [f] v` is `f(a) == v`.
+                Path(ref f, ref v) => eval(&eq(apply(f.clone(), a.clone()), v.clone()), exprs),
+                ref ty => Err(format!("Expected path type, found `{}`", ty)),
+            }
+        }
+        Define(ref a, ref v) => {
+            if let Variable(_) = **a {
+                Ok(Box::new(Define(a.clone(), eval(v, exprs)?)))
+            } else {
+                Err(format!("Expected variable in definition, found `{}`", a))
+            }
+        }
+        Check(ref a, ref b) => Ok(Box::new(Check(a.clone(), eval(b, exprs)?))),
+        Eq(ref l, ref r) => {
+            let new_l = eval(l, exprs)?;
+            let new_r = eval(r, exprs)?;
+            if new_l == new_r {
+                Ok(true_())
+            } else if is_closed(&new_l) && is_closed(&new_r) {
+                Ok(false_())
+            } else {
+                Ok(eq(new_l, new_r))
+            }
+        }
+        Ex(ref f) => {
+            let new_f = expect_value(eval(f, exprs)?)?;
+            match *new_f {
+                // `∃false <=> not`.
+                False => Ok(if_(false_(), true_())),
+                // `∃true <=> id`.
+                True => Ok(if_(true_(), false_())),
+                _ if f != &new_f => eval(&ex(new_f), exprs),
+                // `∃∃f` is the existential path of the tuple
+                // `if((∃f)(true), (∃f)(false))`.
+                Ex(ref g) if is_closed(g) => {
+                    let t = eval(&apply(new_f.clone(), true_()), exprs)?;
+                    let f = eval(&apply(new_f.clone(), false_()), exprs)?;
+                    Ok(ex(if_(t, f)))
+                }
+                _ => Ok(ex(new_f)),
+            }
+        }
+    }
+}
+
+/// Finds the definition of a variable.
+fn definition<'a>(a: &Arc<String>, exprs: &'a [Expression]) -> Option<&'a Expression> {
+    for expr in exprs {
+        if let Define(ref l, ref val) = *expr {
+            if matches!(**l, Variable(ref b) if b == a) {
+                return Some(val)
+            }
+        }
+    }
+    None
+}
+
+/// Returns `true` if the expression refers to `a`, directly or through definitions.
+///
+/// Variables in `visited` have already been checked.
+fn refers_to(
+    expr: &Expression,
+    a: &Arc<String>,
+    exprs: &[Expression],
+    visited: &mut Vec<Arc<String>>
+) -> bool {
+    match *expr {
+        False | True => false,
+        Variable(ref b) => {
+            if b == a {return true};
+            if visited.contains(b) {return false};
+            visited.push(b.clone());
+            match definition(b, exprs) {
+                Some(val) => refers_to(val, a, exprs, visited),
+                None => false,
+            }
+        }
+        If(ref x, ref y) | Path(ref x, ref y) | IsType(ref x, ref y) | Define(ref x, ref y) |
+        Apply(ref x, ref y) | Check(ref x, ref y) | Eq(ref x, ref y) => {
+            refers_to(x, a, exprs, visited) || refers_to(y, a, exprs, visited)
+        }
+        Ex(ref x) => refers_to(x, a, exprs, visited),
+    }
+}
+
+/// Returns `true` if the expression is a boolean or a function of booleans.
+fn is_closed(expr: &Expression) -> bool {
+    match *expr {
+        False | True => true,
+        If(ref t, ref f) => is_closed(t) && is_closed(f),
+        _ => false,
+    }
+}
+
+/// Returns an error if the expression can not be used as a value.
+fn expect_value(expr: Box<Expression>) -> Result<Box<Expression>, String> {
+    match *expr {
+        Path(_, _) | IsType(_, _) | Define(_, _) | Check(_, _) => {
+            Err(format!("Expected value, found `{}`", expr))
+        }
+        _ => Ok(expr),
+    }
+}
+
+/// Reads `n` arguments from a tuple.
+fn to_args(expr: &Expression, n: usize) -> Result<Vec<bool>, String> {
+    let mut res = vec![];
+    let mut expr = expr;
+    while res.len() + 1 < n {
+        match *expr {
+            If(ref a, ref tail) => {
+                res.push(to_bool(a)?);
+                expr = tail;
+            }
+            _ => return Err(format!("Expected tuple of {} arguments, found `{}`", n, expr)),
+        }
+    }
+    if n > 0 {res.push(to_bool(expr)?)};
+    Ok(res)
+}
+
+/// Reads a boolean.
+fn to_bool(expr: &Expression) -> Result<bool, String> {
+    match *expr {
+        True => Ok(true),
+        False => Ok(false),
+        _ => Err(format!("Expected `true` or `false`, found `{}`", expr)),
+    }
+}
+
+/// Creates `false`.
+pub fn false_() -> Box<Expression> {Box::new(False)}
+/// Creates `true`.
+pub fn true_() -> Box<Expression> {Box::new(True)}
+/// Creates a function `if(a, b)`.
+pub fn if_(a: Box<Expression>, b: Box<Expression>) -> Box<Expression> {
+    Box::new(If(a, b))
+}
+/// Creates a variable.
+pub fn var(name: &str) -> Box<Expression> {Box::new(Variable(Arc::new(name.into())))}
+/// Creates a function application `f(value)`.
+pub fn apply(f: Box<Expression>, value: Box<Expression>) -> Box<Expression> {
+    Box::new(Apply(f, value))
+}
+/// Creates a path `[f] value`.
+pub fn path(f: Box<Expression>, value: Box<Expression>) -> Box<Expression> {
+    Box::new(Path(f, value))
+}
+/// Creates a type judgement `a : ty`.
+pub fn is_type(a: Box<Expression>, ty: Box<Expression>) -> Box<Expression> {
+    Box::new(IsType(a, ty))
+}
+/// Creates an equality `a == b`.
+pub fn eq(a: Box<Expression>, b: Box<Expression>) -> Box<Expression> {
+    Box::new(Eq(a, b))
+}
+/// Creates an existential path `(∃a)`.
+pub fn ex(a: Box<Expression>) -> Box<Expression> {
+    Box::new(Ex(a))
+}
+
+/// Creates a tuple `if(a, if(b, ...))` of the arguments `a, b, ...`.
+///
+/// Lambdas are used as tuples: A function of any arity applied to a tuple
+/// takes the first element as its first argument and the tail as the rest.
+/// Since a tuple is a function, applying to a tuple of functions is the same as
+/// applying to the elements of each function in turn.
+///
+/// Returns `None` if there are no arguments.
+pub fn tuple(args: Vec<Box<Expression>>) -> Option<Box<Expression>> {
+    let mut args = args.into_iter().rev();
+    let last = args.next()?;
+    Some(args.fold(last, |tail, a| if_(a, tail)))
+}
+
+/// Derives facts from `start` until every goal is reached.
+///
+/// Returns the facts used to reach the goals,
+/// or all derived facts as error when the goals could not be reached.
+pub fn solve(
+    start: &[Expression],
+    goal: &[Expression],
+    filter: &[Expression],
+) -> Result<Vec<Expression>, Vec<Expression>> {
+    monotonic_solver::solve_and_reduce(start, goal, filter, &[], infer)
+}
+
+/*
+a : [false_1] true
+a : [false_1] false
+
+a : [false_1{[false_1] true}] false
+a : [false_1{[false_1] false}] true
+
+(false, false)
+
+If you use lambdas as tuples, then you can access the first element by
+applying it to `true` and the second element by applying it to `false`.
+So, when evaluating a function and you pass it another function,
+then it should use the first element as the first bit and use the
+second element, a tail, as arguments for the rest.
+
+When you check the following, it seems to operate on two different types:
+
+a : [or] true
+b : [or] id
+
+true : [∃or] true
+id : [∃or] true
+
+One idea is to create a custom evaluation that checks for equality
+with each branch. If there is no equality then it takes the
+existential path of each branch and repeats the procedure.
+When you get to single argument functions,
+you do hard coded checks.
+
+This is a new apply rule but for existential paths.
+
+The double-existential path can be found by creating a tuple
+of the existential path applied to `true` and `false`.
+
+∃∃f <=> if((∃f)(true), (∃f)(false))
+    if(false, false)
+    if(false, true)
+    if(true, false)
+    if(true, true)
+
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bool_defs() -> Vec<Expression> {
+        vec![
+            Define(var("false_1"), if_(false_(), false_())),
+            Define(var("not"), if_(false_(), true_())),
+            Define(var("id"), if_(true_(), false_())),
+            Define(var("true_1"), if_(true_(), true_())),
+            Define(var("and"), if_(var("id"), var("false_1"))),
+            Define(var("or"), if_(var("true_1"), var("id"))),
+        ]
+    }
+
+    #[test]
+    fn eval_apply() {
+        let defs = bool_defs();
+        assert_eq!(eval(&apply(var("not"), true_()), &defs), Ok(false_()));
+        assert_eq!(eval(&apply(apply(var("and"), true_()), true_()), &defs), Ok(true_()));
+        assert_eq!(eval(&apply(apply(var("or"), false_()), false_()), &defs), Ok(false_()));
+        assert_eq!(eval(&apply(ex(var("and")), var("id")), &defs), Ok(true_()));
+        assert_eq!(eval(&apply(ex(var("true_1")), false_()), &defs), Ok(false_()));
+        assert_eq!(eval(&apply(var("not"), var("x")), &defs),
+                   Ok(apply(if_(false_(), true_()), var("x"))));
+        assert_eq!(eval(&apply(var("f"), var("x")), &defs), Ok(apply(var("f"), var("x"))));
+        assert_eq!(eval(&apply(apply(var("not"), true_()), true_()), &defs).unwrap_err(),
+                   "Can not apply `false` to `true`");
+        assert!(eval(&apply(path(var("not"), true_()), true_()), &defs).is_err());
+        assert!(eval(&apply(var("not"), path(var("not"), true_())), &defs).is_err());
+    }
+
+    #[test]
+    fn eval_values() {
+        let defs = bool_defs();
+        assert_eq!(eval(&False, &defs), Ok(false_()));
+        assert_eq!(eval(&True, &defs), Ok(true_()));
+        assert_eq!(eval(&Variable(Arc::new("x".into())), &defs), Ok(var("x")));
+        assert_eq!(eval(&var("id"), &defs), Ok(if_(true_(), false_())));
+        assert_eq!(eval(&if_(var("x"), apply(var("not"), false_())), &defs),
+                   Ok(if_(var("x"), true_())));
+        assert!(eval(&if_(true_(), Box::new(Define(var("x"), true_()))), &defs).is_err());
+        // `∃false <=> not`, `∃true <=> id`.
+        assert_eq!(eval(&ex(false_()), &defs), Ok(if_(false_(), true_())));
+        assert_eq!(eval(&ex(true_()), &defs), Ok(if_(true_(), false_())));
+        assert_eq!(eval(&ex(var("f")), &defs), Ok(ex(var("f"))));
+    }
+
+    #[test]
+    fn eval_variables() {
+        let mut defs = bool_defs();
+        defs.push(Define(var("a"), apply(var("not"), false_())));
+        defs.push(Define(var("b"), var("a")));
+        defs.push(Define(var("c"), apply(var("not"), var("c"))));
+        defs.push(Define(var("d"), var("e")));
+        defs.push(Define(var("e"), var("d")));
+        assert_eq!(eval(&var("a"), &defs), Ok(true_()));
+        assert_eq!(eval(&eq(var("a"), true_()), &defs), Ok(true_()));
+        assert_eq!(eval(&var("b"), &defs), Ok(true_()));
+        // Self-referential definitions are kept.
+        assert_eq!(eval(&var("c"), &defs), Ok(var("c")));
+        assert_eq!(eval(&apply(var("not"), var("c")), &defs),
+                   Ok(apply(if_(false_(), true_()), var("c"))));
+        assert_eq!(eval(&var("d"), &defs), Ok(var("d")));
+    }
+
+    #[test]
+    fn eval_ex_ex() {
+        let defs = bool_defs();
+        let bool_ = |a: bool| if a {true_()} else {false_()};
+        let unary = |a: bool, b: bool| if_(bool_(a), bool_(b));
+        let table = |f: Box<Expression>| -> (Box<Expression>, Box<Expression>) {
+            (eval(&apply(f.clone(), true_()), &defs).unwrap(),
+             eval(&apply(f, false_()), &defs).unwrap())
+        };
+        // The four possible tuples of `∃f`, and their existential paths.
+        assert_eq!(table(ex(unary(false, false))), (false_(), true_()));
+        assert_eq!(table(ex(unary(false, true))), (true_(), true_()));
+        assert_eq!(table(ex(unary(true, false))), (true_(), true_()));
+        assert_eq!(table(ex(unary(true, true))), (true_(), false_()));
+        // `∃∃f` evaluates to the existential path of the tuple of `∃f`.
+        assert_eq!(eval(&ex(ex(var("and"))), &defs), Ok(ex(unary(true, true))));
+        assert_eq!(eval(&ex(ex(var("true_1"))), &defs), Ok(ex(unary(true, false))));
+        assert_eq!(eval(&ex(ex(var("false_1"))), &defs), Ok(ex(unary(false, true))));
+        // `∃∃and <=> id`, `∃∃true_1 <=> true_1`, `∃∃∃and <=> true_1`.
+        assert_eq!(table(ex(ex(var("and")))), (true_(), false_()));
+        assert_eq!(table(ex(ex(var("not")))), (true_(), false_()));
+        assert_eq!(table(ex(ex(var("true_1")))), (true_(), true_()));
+        assert_eq!(table(ex(ex(ex(var("and"))))), (true_(), true_()));
+        assert_eq!(eval(&ex(ex(var("f"))), &defs), Ok(ex(ex(var("f")))));
+    }
+
+    #[test]
+    fn eval_tuples() {
+        let mut defs = bool_defs();
+        defs.push(Define(var("xor"), if_(var("not"), var("id"))));
+        defs.push(Define(var("false_2"), if_(var("false_1"), var("false_1"))));
+        defs.push(Define(var("and3"), if_(var("and"), var("false_2"))));
+        let bool_ = |a: bool| if a {true_()} else {false_()};
+        let tup = |args| tuple(args).unwrap();
+        for &(f, op) in &[
+            ("and", (|a, b| a && b) as fn(bool, bool) -> bool),
+            ("or", |a, b| a || b),
+            ("xor", |a, b| a != b),
+        ] {
+            for &a in &[false, true] {
+                for &b in &[false, true] {
+                    let args = tup(vec![bool_(a), bool_(b)]);
+                    assert_eq!(eval(&apply(var(f), args), &defs), Ok(bool_(op(a, b))));
+                }
+            }
+        }
+        assert_eq!(eval(&apply(var("and3"), tup(vec![true_(), true_(), true_()])), &defs),
+                   Ok(true_()));
+        assert_eq!(eval(&apply(var("and3"), tup(vec![true_(), false_(), true_()])), &defs),
+                   Ok(false_()));
+        // A shorter tuple gives a partial application.
+        assert_eq!(eval(&apply(var("and3"), tup(vec![true_(), true_()])), &defs),
+                   Ok(if_(true_(), false_())));
+        assert_eq!(eval(&apply(var("xor"), tup(vec![true_(), true_(), true_()])), &defs)
+                   .unwrap_err(), "Can not apply `false` to `true`");
+        assert_eq!(tuple(vec![]), None);
+    }
+
+    #[test]
+    fn eval_eq() {
+        let defs = bool_defs();
+        assert_eq!(eval(&eq(apply(var("not"), false_()), true_()), &defs), Ok(true_()));
+        assert_eq!(eval(&eq(true_(), false_()), &defs), Ok(false_()));
+        assert_eq!(eval(&eq(var("id"), var("not")), &defs), Ok(false_()));
+        assert_eq!(eval(&eq(var("x"), true_()), &defs), Ok(eq(var("x"), true_())));
+        assert_eq!(eval(&eq(var("x"), var("x")), &defs), Ok(true_()));
+    }
+
+    #[test]
+    fn eval_statements() {
+        let defs = bool_defs();
+        assert_eq!(eval(&path(var("and"), var("id")), &defs),
+                   Ok(path(if_(if_(true_(), false_()), if_(false_(), false_())),
+                           if_(true_(), false_()))));
+        assert!(eval(&path(var("and"), path(var("not"), true_())), &defs).is_err());
+
+        assert_eq!(eval(&is_type(true_(), path(var("not"), false_())), &defs), Ok(true_()));
+        assert_eq!(eval(&is_type(true_(), path(var("not"), true_())), &defs), Ok(false_()));
+        assert_eq!(eval(&is_type(var("id"), path(ex(var("and")), true_())), &defs), Ok(true_()));
+        assert_eq!(eval(&is_type(var("x"), path(var("f"), true_())), &defs),
+                   Ok(eq(apply(var("f"), var("x")), true_())));
+        assert_eq!(eval(&is_type(true_(), var("not")), &defs).unwrap_err(),
+                   "Expected path type, found `if(false, true)`");
+
+        assert_eq!(eval(&Define(var("x"), apply(var("not"), true_())), &defs),
+                   Ok(Box::new(Define(var("x"), false_()))));
+        assert!(eval(&Define(true_(), false_()), &defs).is_err());
+
+        let a = is_type(true_(), path(var("not"), false_()));
+        assert_eq!(eval(&Check(a.clone(), eq(true_(), true_())), &defs),
+                   Ok(Box::new(Check(a, true_()))));
+    }
+
+    #[test]
+    fn solve_tuples() {
+        let start = parse_lines("
+            false_1 := if(false, false)
+            not := if(false, true)
+            id := if(true, false)
+            true_1 := if(true, true)
+            and := if(id, false_1)
+            or := if(true_1, id)
+            xor := if(not, id)
+            a := false
+            a : [or] id
+            b := if(true, false)
+            b : [xor] true
+            c := if(true, true)
+            c : [and] true
+            d := if(true, true)
+            d : [xor] true
+        ").unwrap();
+        let goal = parse_lines("
+            check `a : [or] id` is `true`
+            check `b : [xor] true` is `true`
+            check `c : [and] true` is `true`
+            check `d : [xor] true` is `false`
+        ").unwrap();
+        assert!(prove(&start, &goal, &[]).unwrap().check(&start).is_ok());
+    }
+
+    #[test]
+    fn solve_paths() {
+        let mut start = bool_defs();
+        start.extend(vec![
+            Define(var("a"), true_()),
+            IsType(var("a"), path(var("and"), var("id"))),
+        ]);
+        let goal = vec![
+            Check(is_type(var("a"), path(var("and"), var("id"))), true_()),
+            Check(is_type(var("id"), path(ex(var("and")), true_())), true_()),
+        ];
+        assert!(solve(&start, &goal, &[]).is_ok());
+    }
+}
@@ -0,0 +1,189 @@
+//! Bridge to the `math_notation` interpreter.
+//!
+//! Boolean functions declared in a program, e.g. `and` from `assets/bool.txt`,
+//! are imported as facts, and conclusions are verified by calling the interpreter.
+//! Arguments of functions with more than one argument are written as tuples, see `tuple`.
+
+use math_notation::program::Program;
+use math_notation::term::Term;
+
+use {eval, if_, is_type, path, to_args, to_bool, tuple, var, Expression};
+use Expression::*;
+
+/// Returns the boolean functions of the program, those with only `bool` in the signature,
+/// except `bool` itself.
+///
+/// Returns the name and arity of each function.
+pub fn bool_functions(program: &Program) -> Result<Vec<(String, usize)>, String> {
+    let bool_ = program.find("bool").ok_or("Could not find `bool`")?;
+    let mut res = vec![];
+    for f in program.functions() {
+        if let Some((args, ret)) = program.signature(f) {
+            if f == bool_ || ret != bool_ || args.iter().any(|&ty| ty != bool_) {continue};
+            if let Some(name) = program.name(f) {
+                res.push((name.to_string(), args.len()));
+            }
+        }
+    }
+    Ok(res)
+}
+
+/// Imports the boolean functions of the program.
+///
+/// Each function gives a definition `f := if(f(true, ...), f(false, ...))`,
+/// and a fact `x : [f] v` for every argument `x`.
+pub fn import(program: &Program) -> Result<Vec<Expression>, String> {
+    let mut res = vec![];
+    for (name, n) in bool_functions(program)? {
+        let f = program.find(&name).unwrap();
+        let mut facts = vec![];
+        let def = define(program, f, n, &mut vec![], &mut facts)?;
+        res.push(Define(var(&name), def));
+        for (args, v) in facts {
+            res.push(*is_type(args, path(var(&name), v)));
+        }
+    }
+    Ok(res)
+}
+
+fn define(
+    program: &Program,
+    f: usize,
+    n: usize,
+    args: &mut Vec<bool>,
+    facts: &mut Vec<(Box<Expression>, Box<Expression>)>
+) -> Result<Box<Expression>, String> {
+    if args.len() == n {
+        let terms: Vec<Term> = args.iter().map(|&a| Term::sym(bool_name(a))).collect();
+        let v = from_term(&program.call(f, &terms)?)?;
+        if let Some(args) = tuple(args.iter().map(|&a| from_bool(a)).collect()) {
+            facts.push((args, v.clone()));
+        }
+        return Ok(v);
+    }
+    let mut branch = |val: bool| -> Result<Box<Expression>, String> {
+        args.push(val);
+        let res = define(program, f, n, args, facts);
+        args.pop();
+        res
+    };
+    let t = branch(true)?;
+    let f = branch(false)?;
+    Ok(if_(t, f))
+}
+
+/// Verifies a fact by calling the interpreter.
+///
+/// Variables are evaluated using the definitions in `story`.
+/// Supports `a : [f] v`, `a : [(∃f)] v` and `check `x` is `b`` of these,
+/// where `f` is a boolean function of the program.
+pub fn verify(program: &Program, story: &[Expression], fact: &Expression) -> Result<bool, String> {
+    match *fact {
+        Check(ref x, ref b) => {
+            let b = to_bool(&*eval(b, story)?)?;
+            Ok(verify(program, story, x)? == b)
+        }
+        IsType(ref a, ref ty) => {
+            let (f, v) = match **ty {
+                Path(ref f, ref v) => (f, to_bool(&*eval(v, story)?)?),
+                _ => return Err(format!("Expected path type in `{}`", fact)),
+            };
+            let a = eval(a, story)?;
+            match **f {
+                Variable(ref name) => {
+                    let (f, n) = find(program, name)?;
+                    let args = to_args(&a, n)?;
+                    let terms: Vec<Term> = args.iter().map(|&a| Term::sym(bool_name(a))).collect();
+                    Ok(to_bool(&*from_term(&program.call(f, &terms)?)?)? == v)
+                }
+                Ex(ref g) => {
+                    let name = match **g {
+                        Variable(ref name) => name,
+                        _ => return Err(format!("Expected function name in `{}`", fact)),
+                    };
+                    let (f, _) = find(program, name)?;
+                    let a = Term::sym(bool_name(to_bool(&a)?));
+                    Ok(program.existential_path(f)?.contains(&a) == v)
+                }
+                _ => Err(format!("Expected function name in `{}`", fact)),
+            }
+        }
+        _ => Err(format!("Can not verify `{}`", fact)),
+    }
+}
+
+fn find(program: &Program, name: &str) -> Result<(usize, usize), String> {
+    let n = bool_functions(program)?.into_iter().find(|f| f.0 == name)
+        .ok_or_else(|| format!("Could not find boolean function `{}`", name))?.1;
+    Ok((program.find(name).unwrap(), n))
+}
+
+fn bool_name(val: bool) -> &'static str {
+    if val {"true"} else {"false"}
+}
+
+fn from_bool(val: bool) -> Box<Expression> {
+    Box::new(if val {True} else {False})
+}
+
+fn from_term(term: &Term) -> Result<Box<Expression>, String> {
+    match *term {
+        Term::App(ref name, ref args) if args.is_empty() && &**name == "true" => Ok(from_bool(true)),
+        Term::App(ref name, ref args) if args.is_empty() && &**name == "false" => Ok(from_bool(false)),
+        _ => Err(format!("Expected `true` or `false`, found `{}`", term)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {parse, parse_lines, prove};
+
+    fn bool_program() -> Program {
+        Program::from_source(include_str!("../../../assets/bool.txt")).unwrap()
+    }
+
+    #[test]
+    fn import_bool() {
+        let program = bool_program();
+        let mut names = bool_functions(&program).unwrap();
+        names.sort();
+        assert_eq!(names, vec![("and".into(), 2), ("not".into(), 1), ("or".into(), 2)]);
+        let facts = import(&program).unwrap();
+        assert!(facts.contains(&parse("not := if(false, true)").unwrap()));
+        assert!(facts.contains(&parse("and := if(if(true, false), if(false, false))").unwrap()));
+        assert!(facts.contains(&parse("true : [not] false").unwrap()));
+        assert!(facts.contains(&parse("if(false, true) : [or] true").unwrap()));
+        assert_eq!(facts.len(), 3 + 2 + 4 + 4);
+    }
+
+    #[test]
+    fn verify_conclusions() {
+        let program = bool_program();
+        let mut start = import(&program).unwrap();
+        start.extend(parse_lines("
+            a := if(true, true)
+            a : [and] false
+            b := false
+            b : [not] true
+        ").unwrap());
+        let goal = parse_lines("
+            check `a : [and] false` is `false`
+            check `b : [not] true` is `true`
+            check `false : [(∃or)] true` is `true`
+        ").unwrap();
+        let proof = prove(&start, &goal, &[]).unwrap();
+        let story = proof.facts();
+        for fact in &goal {
+            assert_eq!(verify(&program, &story, fact), Ok(true));
+        }
+        let wrong = parse("check `b : [not] true` is `false`").unwrap();
+        assert_eq!(verify(&program, &story, &wrong), Ok(false));
+        assert_eq!(verify(&program, &story, &parse("if(true, false) : [and] true").unwrap()),
+                   Ok(false));
+        assert_eq!(verify(&program, &story, &parse("false : [(∃or)] false").unwrap()),
+                   Ok(false));
+        assert!(verify(&program, &story, &parse("true : [and] true").unwrap()).is_err());
+        assert!(verify(&program, &story, &parse("true : [xor] true").unwrap()).is_err());
+    }
+}
@@ -0,0 +1,312 @@
+//! Proof objects, recording which rule derived each fact from which premises.
+//!
+//! A proof is a list of steps, where premises refer to earlier steps by index.
+//! This is a proof tree where shared subproofs are stored once.
+//! It is written one step per line, e.g.
+//!
+//! ```text
+//! 0: axiom a := true
+//! 1: axiom a : [id] true
+//! 2: check_define(1, 0) check `a : [id] true` is `true`
+//! ```
+//!
+//! Proofs are checked by replaying each rule, without search.
+//! Axioms are checked against the start facts.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use {Expression, Rules};
+
+/// The rule name of start facts.
+pub const AXIOM: &str = "axiom";
+
+/// Stores a step of a proof.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Step {
+    /// The fact.
+    pub fact: Expression,
+    /// The name of the rule that derived the fact, or `axiom` for start facts.
+    pub rule: String,
+    /// The indices of the premises, in the order required by the rule.
+    pub premises: Vec<usize>,
+}
+
+/// Stores a proof.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Proof {
+    /// The steps, where premises come before the steps using them.
+    pub steps: Vec<Step>,
+}
+
+impl Proof {
+    /// Returns the facts of the proof.
+    pub fn facts(&self) -> Vec<Expression> {
+        self.steps.iter().map(|step| step.fact.clone()).collect()
+    }
+
+    /// Returns `true` if the proof contains the fact.
+    pub fn proves(&self, fact: &Expression) -> bool {
+        self.steps.iter().any(|step| &step.fact == fact)
+    }
+
+    /// Checks every step of the proof from the start facts using the standard rules.
+    pub fn check(&self, start: &[Expression]) -> Result<(), String> {
+        self.check_with(&Rules::standard(), start)
+    }
+
+    /// Checks every step of the proof by applying its rule to the premises.
+    ///
+    /// Definitions are looked up in the facts before each step.
+    /// Returns an error if a step uses a rule that is not enabled,
+    /// or if an axiom is not one of the start facts.
+    pub fn check_with(&self, rules: &Rules, start: &[Expression]) -> Result<(), String> {
+        let facts = self.facts();
+        for (i, step) in self.steps.iter().enumerate() {
+            if step.rule == AXIOM {
+                if !step.premises.is_empty() {
+                    return Err(format!("Step {}: Axiom has premises", i));
+                }
+                if !start.contains(&step.fact) {
+                    return Err(format!("Step {}: `{}` is not a start fact", i, step.fact));
+                }
+                continue;
+            }
+            if let Some(&j) = step.premises.iter().find(|&&j| j >= i) {
+                return Err(format!("Step {}: Premise {} is not an earlier step", i, j));
+            }
+            let premises: Vec<&Expression> = step.premises.iter().map(|&j| &facts[j]).collect();
+            let rule = rules.get(&step.rule)
+                .ok_or_else(|| format!("Step {}: Rule `{}` is not enabled", i, step.rule))?;
+            let fact = rule.apply(&premises, &facts[..i])
+                .map_err(|err| format!("Step {}: {}", i, err))?;
+            if fact != step.fact {
+                return Err(format!("Step {}: Expected `{}`, found `{}`", i, fact, step.fact));
+            }
+        }
+        Ok(())
+    }
+
+    /// Parses a proof written by `Display`.
+    pub fn parse(source: &str) -> Result<Proof, String> {
+        let mut steps = vec![];
+        for (i, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {continue};
+            let step = parse_step(line, steps.len()).map_err(|err| format!("Line {}: {}", i + 1, err))?;
+            steps.push(step);
+        }
+        Ok(Proof {steps})
+    }
+}
+
+fn parse_step(line: &str, index: usize) -> Result<Step, String> {
+    let colon = line.find(':').ok_or("Expected `:`")?;
+    if line[..colon].trim().parse() != Ok(index) {
+        return Err(format!("Expected step {}", index));
+    }
+    let rest = line[colon + 1..].trim_start();
+    let end = rest.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(rest.len());
+    if end == 0 {
+        return Err("Expected rule".into());
+    }
+    let rule = rest[..end].to_string();
+    let mut rest = &rest[end..];
+    let mut premises = vec![];
+    if rest.starts_with('(') {
+        let close = rest.find(')').ok_or("Expected `)`")?;
+        for premise in rest[1..close].split(',') {
+            premises.push(premise.trim().parse()
+                .map_err(|_| format!("Expected premise index, found `{}`", premise.trim()))?);
+        }
+        rest = &rest[close + 1..];
+    }
+    Ok(Step {fact: ::parse(rest)?, rule, premises})
+}
+
+impl fmt::Display for Proof {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, step) in self.steps.iter().enumerate() {
+            write!(f, "{}: {}", i, step.rule)?;
+            if !step.premises.is_empty() {
+                let premises: Vec<String> = step.premises.iter().map(|j| j.to_string()).collect();
+                write!(f, "({})", premises.join(", "))?;
+            }
+            writeln!(f, " {}", step.fact)?;
+        }
+        Ok(())
+    }
+}
+
+/// Infers a new step from the story using the enabled rules.
+///
+/// Premises are indices into the story.
+pub fn infer_step(
+    rules: &Rules,
+    cache: &HashSet<Expression>,
+    filter_cache: &HashSet<Expression>,
+    story: &[Expression]
+) -> Option<Step> {
+    let can_add = |new_expr: &Expression| {
+        !cache.contains(new_expr) &&
+        !filter_cache.contains(new_expr)
+    };
+
+    for rule in rules.enabled() {
+        for premises in rule.candidates(story) {
+            let premise_facts: Vec<&Expression> = premises.iter().map(|&j| &story[j]).collect();
+            if let Ok(fact) = rule.apply(&premise_facts, story) {
+                if can_add(&fact) {
+                    return Some(Step {fact, rule: rule.name().into(), premises});
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// Derives facts from `start` using the standard rules, recording the proof.
+pub fn prove(
+    start: &[Expression],
+    goal: &[Expression],
+    filter: &[Expression],
+) -> Result<Proof, Vec<Expression>> {
+    prove_with(&Rules::standard(), start, goal, filter)
+}
+
+/// Derives facts from `start` until every goal is reached, recording the proof.
+///
+/// The proof keeps the start facts and the steps needed by the goals.
+/// Returns all derived facts as error when the goals could not be reached.
+pub fn prove_with(
+    rules: &Rules,
+    start: &[Expression],
+    goal: &[Expression],
+    filter: &[Expression],
+) -> Result<Proof, Vec<Expression>> {
+    let mut cache: HashSet<Expression> = start.iter().cloned().collect();
+    let filter_cache: HashSet<Expression> = filter.iter().cloned().collect();
+    let mut story: Vec<Expression> = start.into();
+    let mut steps: Vec<Step> = start.iter().map(|fact| {
+        Step {fact: fact.clone(), rule: AXIOM.into(), premises: vec![]}
+    }).collect();
+    while !goal.iter().all(|e| cache.contains(e)) {
+        match infer_step(rules, &cache, &filter_cache, &story) {
+            Some(step) => {
+                story.push(step.fact.clone());
+                cache.insert(step.fact.clone());
+                steps.push(step);
+            }
+            None => return Err(story),
+        }
+    }
+
+    // Keep the steps that goals depend on.
+    let mut needed: Vec<bool> = (0..steps.len()).map(|i| i < start.len()).collect();
+    let mut stack: Vec<usize> = goal.iter()
+        .map(|e| story.iter().position(|f| f == e).unwrap()).collect();
+    while let Some(i) = stack.pop() {
+        if needed[i] {continue};
+        needed[i] = true;
+        stack.extend(&steps[i].premises);
+    }
+    let mut new_index = vec![0; steps.len()];
+    let mut res = vec![];
+    for (i, mut step) in steps.into_iter().enumerate() {
+        if !needed[i] {continue};
+        new_index[i] = res.len();
+        step.premises = step.premises.iter().map(|&j| new_index[j]).collect();
+        res.push(step);
+    }
+    Ok(Proof {steps: res})
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {parse_lines, var, if_, false_, is_type, path};
+    use Expression::*;
+
+    fn example() -> (Vec<Expression>, Vec<Expression>) {
+        let start = parse_lines("
+            false_1 := if(false, false)
+            true_1 := if(true, true)
+            id := if(true, false)
+            and := if(id, false_1)
+            a := true
+            a : [and] id
+            b : [and] true
+        ").unwrap();
+        let goal = parse_lines("
+            check `a : [and] id` is `true`
+            check `true : [(∃and)] true` is `true`
+        ").unwrap();
+        (start, goal)
+    }
+
+    #[test]
+    fn prove_and_check() {
+        let (start, goal) = example();
+        let proof = prove(&start, &goal, &[]).unwrap();
+        assert!(goal.iter().all(|e| proof.proves(e)));
+        assert_eq!(proof.steps.len(), start.len() + 2);
+        assert_eq!(proof.steps[7].rule, "check_define");
+        assert_eq!(proof.steps[7].premises, vec![5, 4]);
+        assert_eq!(proof.steps[8].rule, "check_ex");
+        assert_eq!(proof.steps[8].premises, vec![6]);
+        assert_eq!(proof.check(&start), Ok(()));
+    }
+
+    #[test]
+    fn round_trip() {
+        let (start, goal) = example();
+        let proof = prove(&start, &goal, &[]).unwrap();
+        let text = proof.to_string();
+        assert!(text.contains("7: check_define(5, 4) check `a : [and] id` is `true`\n"));
+        assert_eq!(Proof::parse(&text), Ok(proof));
+        assert!(Proof::parse("0: guess true").unwrap().check(&start).is_err());
+        assert!(Proof::parse("1: axiom true").is_err());
+    }
+
+    #[test]
+    fn reject() {
+        let (start, goal) = example();
+        let proof = prove(&start, &goal, &[]).unwrap();
+
+        let mut wrong = proof.clone();
+        wrong.steps[7].fact = Check(is_type(var("a"), path(var("and"), var("id"))), false_());
+        assert_eq!(wrong.check(&start).unwrap_err(),
+                   "Step 7: Expected `check `a : [and] id` is `true``, \
+                    found `check `a : [and] id` is `false``");
+
+        let mut wrong = proof.clone();
+        wrong.steps[7].premises = vec![6, 4];
+        assert_eq!(wrong.check(&start).unwrap_err(), "Step 7: Rule `check_define` does not match premises");
+
+        let mut wrong = proof.clone();
+        wrong.steps[7].premises = vec![8, 4];
+        assert_eq!(wrong.check(&start).unwrap_err(), "Step 7: Premise 8 is not an earlier step");
+
+        let mut wrong = proof;
+        wrong.steps[0] = Step {
+            fact: Define(var("false_1"), if_(false_(), false_())),
+            rule: "eval_define".into(),
+            premises: vec![],
+        };
+        assert!(wrong.check(&start).is_err());
+
+        // A fact can not be assumed without being a start fact.
+        let forged = Proof::parse("0: axiom check `a : [and] id` is `false`").unwrap();
+        assert_eq!(forged.check(&start).unwrap_err(),
+                   "Step 0: `check `a : [and] id` is `false`` is not a start fact");
+        assert_eq!(forged.check(&forged.facts()), Ok(()));
+    }
+
+    #[test]
+    fn unsolvable() {
+        let (start, _) = example();
+        let goal = parse_lines("check `b : [and] true` is `true`").unwrap();
+        assert!(prove(&start, &goal, &[]).is_err());
+    }
+}
@@ -0,0 +1,221 @@
+//! Refutation of goals by counterexamples over finite Boolean domains.
+//!
+//! When a goal can not be proved, the free variables of the goal,
+//! those without a definition, are assigned booleans and tuples of booleans.
+//! A goal that evaluates to `false` for some assignment is refuted.
+
+use std::fmt;
+use std::sync::Arc;
+
+use {eval, false_, prove_with, true_, tuple, Expression, Proof, Rules};
+use Expression::*;
+
+/// Stores an assignment of free variables that makes a goal false.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Counterexample {
+    /// The refuted goal.
+    pub goal: Expression,
+    /// The values of the free variables.
+    pub assignment: Vec<(Arc<String>, Expression)>,
+}
+
+impl fmt::Display for Counterexample {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.assignment.is_empty() {
+            return write!(f, "`{}` is false", self.goal);
+        }
+        let assignment: Vec<String> = self.assignment.iter()
+            .map(|(name, val)| format!("{} := {}", name, val)).collect();
+        write!(f, "`{}` is false for {}", self.goal, assignment.join(", "))
+    }
+}
+
+/// The outcome of proving goals.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Outcome {
+    /// The goals were proved.
+    Proved(Proof),
+    /// A goal is false.
+    Refuted(Counterexample),
+    /// The goals could not be proved with the rules, nor refuted.
+    ///
+    /// Stores the derived facts.
+    Unknown(Vec<Expression>),
+}
+
+/// Returns the tuples of booleans with up to `max_arity` elements.
+pub fn domain(max_arity: usize) -> Vec<Box<Expression>> {
+    let mut res = vec![];
+    for n in 1..max_arity + 1 {
+        for i in 0..1 << n {
+            res.extend(tuple((0..n).map(|k| {
+                if i & (1 << (n - 1 - k)) != 0 {true_()} else {false_()}
+            }).collect()));
+        }
+    }
+    res
+}
+
+/// Returns the free variables of the expression, those without a definition in `story`.
+pub fn free_variables(expr: &Expression, story: &[Expression]) -> Vec<Arc<String>> {
+    fn collect(expr: &Expression, story: &[Expression], res: &mut Vec<Arc<String>>) {
+        match *expr {
+            False | True => {}
+            Variable(ref name) => {
+                let defined = story.iter().any(|e| match *e {
+                    Define(ref l, _) => matches!(**l, Variable(ref l) if l == name),
+                    _ => false,
+                });
+                if !defined && !res.contains(name) {res.push(name.clone())};
+            }
+            Ex(ref a) => collect(a, story, res),
+            If(ref a, ref b) | Path(ref a, ref b) | IsType(ref a, ref b) |
+            Define(ref a, ref b) | Apply(ref a, ref b) | Check(ref a, ref b) |
+            Eq(ref a, ref b) => {
+                collect(a, story, res);
+                collect(b, story, res);
+            }
+        }
+    }
+
+    let mut res = vec![];
+    collect(expr, story, &mut res);
+    res
+}
+
+/// Searches for an assignment of the free variables that makes the goal false.
+///
+/// The free variables are assigned tuples of booleans with up to `max_arity` elements.
+/// A goal `check `x` is `b`` is refuted when `x` evaluates to another boolean than `b`.
+pub fn refute(goal: &Expression, story: &[Expression], max_arity: usize) -> Option<Counterexample> {
+    let vars = free_variables(goal, story);
+    let values = domain(max_arity);
+    if !vars.is_empty() && values.is_empty() {return None};
+    let mut ind = vec![0; vars.len()];
+    'ind: loop {
+        let mut defs: Vec<Expression> = vars.iter().zip(&ind)
+            .map(|(name, &i)| Define(Box::new(Variable(name.clone())), values[i].clone()))
+            .collect();
+        defs.extend_from_slice(story);
+        if is_false(goal, &defs) {
+            return Some(Counterexample {
+                goal: goal.clone(),
+                assignment: vars.iter().zip(&ind)
+                    .map(|(name, &i)| (name.clone(), (*values[i]).clone())).collect(),
+            });
+        }
+        // Go to next assignment.
+        for k in (0..ind.len()).rev() {
+            ind[k] += 1;
+            if ind[k] < values.len() {continue 'ind};
+            ind[k] = 0;
+        }
+        return None;
+    }
+}
+
+fn is_false(goal: &Expression, story: &[Expression]) -> bool {
+    let is_bool = |e: &Expression| matches!(*e, True | False);
+    match *goal {
+        Check(ref x, ref b) => match (eval(x, story), eval(b, story)) {
+            (Ok(x), Ok(b)) => is_bool(&x) && is_bool(&b) && x != b,
+            _ => false,
+        },
+        _ => eval(goal, story).map(|x| *x == False).unwrap_or(false),
+    }
+}
+
+/// Proves the goals, or searches for a counterexample when they can not be proved.
+///
+/// `Refuted` means that a goal is false, while `Unknown` means that
+/// the goals could not be proved with the rules, but no counterexample was found.
+pub fn prove_or_refute(
+    rules: &Rules,
+    start: &[Expression],
+    goal: &[Expression],
+    filter: &[Expression],
+    max_arity: usize,
+) -> Outcome {
+    match prove_with(rules, start, goal, filter) {
+        Ok(proof) => Outcome::Proved(proof),
+        Err(story) => {
+            for g in goal {
+                if story.contains(g) {continue};
+                if let Some(counterexample) = refute(g, &story, max_arity) {
+                    return Outcome::Refuted(counterexample);
+                }
+            }
+            Outcome::Unknown(story)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {parse, parse_lines};
+
+    fn bool_defs() -> Vec<Expression> {
+        parse_lines("
+            false_1 := if(false, false)
+            not := if(false, true)
+            id := if(true, false)
+            true_1 := if(true, true)
+            and := if(id, false_1)
+            or := if(true_1, id)
+        ").unwrap()
+    }
+
+    #[test]
+    fn domains() {
+        assert_eq!(domain(0), vec![]);
+        assert_eq!(domain(1), vec![false_(), true_()]);
+        assert_eq!(domain(2).len(), 2 + 4);
+        assert!(domain(2).contains(&tuple(vec![true_(), false_()]).unwrap()));
+    }
+
+    #[test]
+    fn refute_path() {
+        let defs = bool_defs();
+        let goal = parse("a : [and] id").unwrap();
+        assert_eq!(free_variables(&goal, &defs), vec![Arc::new("a".to_string())]);
+        let counterexample = refute(&goal, &defs, 1).unwrap();
+        assert_eq!(counterexample.assignment, vec![(Arc::new("a".into()), False)]);
+        assert_eq!(counterexample.to_string(), "`a : [and] id` is false for a := false");
+
+        // `or(a, b) == or(b, a)` holds for every assignment.
+        assert_eq!(refute(&parse("or(a)(b) == or(b)(a)").unwrap(), &defs, 1), None);
+        let goal = parse("check `x : [and] false` is `false`").unwrap();
+        assert_eq!(refute(&goal, &defs, 2).unwrap().assignment,
+                   vec![(Arc::new("x".into()), *tuple(vec![false_(), false_()]).unwrap())]);
+    }
+
+    #[test]
+    fn outcomes() {
+        let rules = Rules::standard();
+        let mut start = bool_defs();
+        start.extend(parse_lines("
+            a := true
+            a : [and] id
+        ").unwrap());
+
+        let goal = parse_lines("check `a : [and] id` is `true`").unwrap();
+        assert!(matches!(prove_or_refute(&rules, &start, &goal, &[], 2), Outcome::Proved(_)));
+
+        let goal = parse_lines("check `a : [and] id` is `false`").unwrap();
+        match prove_or_refute(&rules, &start, &goal, &[], 2) {
+            Outcome::Refuted(c) => assert_eq!(c.to_string(),
+                "`check `a : [and] id` is `false`` is false"),
+            x => panic!("Expected refutation, found {:?}", x),
+        }
+
+        let goal = parse_lines("b : [and] id").unwrap();
+        assert!(matches!(prove_or_refute(&rules, &start, &goal, &[], 2),
+                         Outcome::Refuted(_)));
+
+        // True, but not derived by the rules.
+        let goal = parse_lines("true : [and] id").unwrap();
+        assert!(matches!(prove_or_refute(&rules, &start, &goal, &[], 2),
+                         Outcome::Unknown(_)));
+    }
+}
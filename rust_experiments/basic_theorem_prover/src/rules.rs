@@ -0,0 +1,286 @@
+//! Inference rules and a registry to enable or disable them per run.
+//!
+//! A rule finds candidate premises in the story and derives a fact from them.
+//! New rules are added by implementing `Rule` and registering it in `Rules`.
+
+use std::collections::HashSet;
+
+use {eval, apply, eq, ex, is_type, path, true_, Expression};
+use Expression::*;
+
+/// An inference rule.
+pub trait Rule {
+    /// Returns the name of the rule, used in proofs.
+    fn name(&self) -> &str;
+
+    /// Returns lists of premise indices into the story that the rule might apply to.
+    fn candidates(&self, story: &[Expression]) -> Vec<Vec<usize>>;
+
+    /// Applies the rule to premises, using the definitions in `story`.
+    ///
+    /// Returns an error if the premises do not match the rule.
+    fn apply(&self, premises: &[&Expression], story: &[Expression]) -> Result<Expression, String>;
+}
+
+fn no_match(rule: &dyn Rule) -> String {
+    format!("Rule `{}` does not match premises", rule.name())
+}
+
+/// Returns the indices of facts `a : [f] v`.
+fn path_types(story: &[Expression]) -> Vec<usize> {
+    story.iter().enumerate().filter_map(|(i, expr)| {
+        if let IsType(_, ref ty) = *expr {
+            if let Path(_, _) = **ty {return Some(i)};
+        }
+        None
+    }).collect()
+}
+
+/// `a := b` gives `a := eval(b)`.
+pub struct EvalDefine;
+
+impl Rule for EvalDefine {
+    fn name(&self) -> &str {"eval_define"}
+
+    fn candidates(&self, story: &[Expression]) -> Vec<Vec<usize>> {
+        story.iter().enumerate()
+            .filter(|&(_, expr)| matches!(*expr, Define(_, _)))
+            .map(|(i, _)| vec![i]).collect()
+    }
+
+    fn apply(&self, premises: &[&Expression], story: &[Expression]) -> Result<Expression, String> {
+        match *premises {
+            [Define(l, r)] => Ok(Define(l.clone(), eval(r, story)?)),
+            _ => Err(no_match(self)),
+        }
+    }
+}
+
+/// `a : [f] v` and `a := b` give `check `a : [f] v` is `eval(f(b) == v)``.
+pub struct CheckDefine;
+
+impl Rule for CheckDefine {
+    fn name(&self) -> &str {"check_define"}
+
+    fn candidates(&self, story: &[Expression]) -> Vec<Vec<usize>> {
+        let mut res = vec![];
+        for i in path_types(story) {
+            if let IsType(ref l, _) = story[i] {
+                for (j, expr) in story.iter().enumerate() {
+                    if let Define(ref l2, _) = *expr {
+                        if l2 == l {res.push(vec![i, j])};
+                    }
+                }
+            }
+        }
+        res
+    }
+
+    fn apply(&self, premises: &[&Expression], story: &[Expression]) -> Result<Expression, String> {
+        if let [a, Define(l2, val)] = *premises {
+            if let IsType(ref l, ref ty) = *a {
+                if let Path(ref f, ref v) = **ty {
+                    if l2 == l {
+                        let res = eval(&eq(apply(f.clone(), val.clone()), v.clone()), story)?;
+                        return Ok(Check(Box::new(a.clone()), res));
+                    }
+                }
+            }
+        }
+        Err(no_match(self))
+    }
+}
+
+/// `a : [f] v` gives `check `v : [(∃f)] true` is `eval((∃f)(v) == true)``.
+pub struct CheckEx;
+
+impl Rule for CheckEx {
+    fn name(&self) -> &str {"check_ex"}
+
+    fn candidates(&self, story: &[Expression]) -> Vec<Vec<usize>> {
+        path_types(story).into_iter().map(|i| vec![i]).collect()
+    }
+
+    fn apply(&self, premises: &[&Expression], story: &[Expression]) -> Result<Expression, String> {
+        if let [IsType(_, ty)] = *premises {
+            if let Path(ref f, ref v) = **ty {
+                let res = eval(&eq(apply(ex(f.clone()), v.clone()), true_()), story)?;
+                return Ok(Check(is_type(v.clone(), path(ex(f.clone()), true_())), res));
+            }
+        }
+        Err(no_match(self))
+    }
+}
+
+/// `a : [f] v` gives `v : [(∃f)] true`.
+///
+/// This introduces existential paths as facts, without evaluating them.
+/// It does not apply when `f` is an existential path,
+/// since it would otherwise introduce `∃∃f`, `∃∃∃f` etc. without end.
+/// It is not enabled by default.
+pub struct ExIntro;
+
+impl Rule for ExIntro {
+    fn name(&self) -> &str {"ex_intro"}
+
+    fn candidates(&self, story: &[Expression]) -> Vec<Vec<usize>> {
+        path_types(story).into_iter().map(|i| vec![i]).collect()
+    }
+
+    fn apply(&self, premises: &[&Expression], _story: &[Expression]) -> Result<Expression, String> {
+        if let [IsType(_, ty)] = *premises {
+            if let Path(ref f, ref v) = **ty {
+                if let Ex(_) = **f {return Err(no_match(self))};
+                return Ok(IsType(v.clone(), path(ex(f.clone()), true_())));
+            }
+        }
+        Err(no_match(self))
+    }
+}
+
+/// Stores the registered rules and which of them are enabled.
+pub struct Rules {
+    rules: Vec<Box<dyn Rule>>,
+    disabled: HashSet<String>,
+}
+
+impl Rules {
+    /// Creates a registry without rules.
+    pub fn new() -> Rules {
+        Rules {rules: vec![], disabled: HashSet::new()}
+    }
+
+    /// Creates a registry with the standard rules enabled.
+    ///
+    /// `ex_intro` is registered, but disabled.
+    pub fn standard() -> Rules {
+        let mut rules = Rules::new();
+        rules.add(EvalDefine);
+        rules.add(CheckDefine);
+        rules.add(CheckEx);
+        rules.add(ExIntro);
+        rules.disabled.insert("ex_intro".into());
+        rules
+    }
+
+    /// Registers a rule, replacing any rule with the same name.
+    ///
+    /// The rule is enabled.
+    pub fn add<R: Rule + 'static>(&mut self, rule: R) {
+        let name = rule.name().to_string();
+        self.rules.retain(|r| r.name() != name);
+        self.disabled.remove(&name);
+        self.rules.push(Box::new(rule));
+    }
+
+    /// Enables a rule by name.
+    pub fn enable(&mut self, name: &str) -> Result<(), String> {
+        self.get_any(name)?;
+        self.disabled.remove(name);
+        Ok(())
+    }
+
+    /// Disables a rule by name.
+    pub fn disable(&mut self, name: &str) -> Result<(), String> {
+        self.get_any(name)?;
+        self.disabled.insert(name.into());
+        Ok(())
+    }
+
+    /// Returns `true` if the rule is registered and enabled.
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// Returns the names of the registered rules.
+    pub fn names(&self) -> Vec<&str> {
+        self.rules.iter().map(|r| r.name()).collect()
+    }
+
+    /// Returns an enabled rule by name.
+    pub fn get(&self, name: &str) -> Option<&dyn Rule> {
+        if self.disabled.contains(name) {return None};
+        self.get_any(name).ok()
+    }
+
+    /// Returns the enabled rules in the order they were registered.
+    pub fn enabled(&self) -> Vec<&dyn Rule> {
+        self.rules.iter().map(|r| &**r).filter(|r| !self.disabled.contains(r.name())).collect()
+    }
+
+    fn get_any(&self, name: &str) -> Result<&dyn Rule, String> {
+        self.rules.iter().map(|r| &**r).find(|r| r.name() == name)
+            .ok_or_else(|| format!("Unknown rule `{}`", name))
+    }
+}
+
+impl Default for Rules {
+    fn default() -> Rules {Rules::standard()}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {parse, parse_lines, prove_with};
+
+    // `a == b` gives `check `a == b` is `eval(a == b)``.
+    struct CheckEq;
+
+    impl Rule for CheckEq {
+        fn name(&self) -> &str {"check_eq"}
+
+        fn candidates(&self, story: &[Expression]) -> Vec<Vec<usize>> {
+            story.iter().enumerate()
+                .filter(|&(_, expr)| matches!(*expr, Eq(_, _)))
+                .map(|(i, _)| vec![i]).collect()
+        }
+
+        fn apply(&self, premises: &[&Expression], story: &[Expression]) -> Result<Expression, String> {
+            match *premises {
+                [e @ Eq(_, _)] => Ok(Check(Box::new(e.clone()), eval(e, story)?)),
+                _ => Err(no_match(self)),
+            }
+        }
+    }
+
+    #[test]
+    fn registry() {
+        let mut rules = Rules::standard();
+        assert_eq!(rules.names(), vec!["eval_define", "check_define", "check_ex", "ex_intro"]);
+        assert!(rules.is_enabled("check_ex"));
+        assert!(!rules.is_enabled("ex_intro"));
+        assert_eq!(rules.enabled().len(), 3);
+        rules.disable("check_ex").unwrap();
+        assert!(rules.get("check_ex").is_none());
+        rules.enable("ex_intro").unwrap();
+        assert!(rules.is_enabled("ex_intro"));
+        assert_eq!(rules.enable("foo").unwrap_err(), "Unknown rule `foo`");
+        rules.add(CheckEq);
+        assert!(rules.is_enabled("check_eq"));
+    }
+
+    #[test]
+    fn custom_rules() {
+        let start = parse_lines("
+            not := if(false, true)
+            not(true) == false
+            a : [not] true
+        ").unwrap();
+        let goal = parse_lines("
+            check `not(true) == false` is `true`
+            true : [(∃not)] true
+        ").unwrap();
+        let mut rules = Rules::standard();
+        assert!(prove_with(&rules, &start, &goal, &[]).is_err());
+        rules.add(CheckEq);
+        rules.enable("ex_intro").unwrap();
+        let proof = prove_with(&rules, &start, &goal, &[]).unwrap();
+        assert_eq!(proof.check_with(&rules, &start), Ok(()));
+        // The proof is rejected when a rule it uses is disabled.
+        rules.disable("check_eq").unwrap();
+        assert_eq!(proof.check_with(&rules, &start).unwrap_err(), "Step 4: Rule `check_eq` is not enabled");
+        assert_eq!(ExIntro.apply(&[&parse("a : [f] b").unwrap()], &[]),
+                   parse("b : [(∃f)] true"));
+        assert!(ExIntro.apply(&[&parse("a : [(∃f)] true").unwrap()], &[]).is_err());
+    }
+}
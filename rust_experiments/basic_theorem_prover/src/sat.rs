@@ -0,0 +1,188 @@
+//! Decision of claims about boolean functions by the SAT solver of `boolean_paths`.
+//!
+//! An alternative to evaluation and `notation::verify`, that does not enumerate
+//! the arguments of functions. Functions are evaluated using the definitions
+//! in the story and converted to `boolean_paths::Expr`, which uses the same
+//! convention `if(a, b)` with the first argument outermost.
+
+use std::sync::Arc;
+
+use boolean_paths::sat::{self, Lit, Solver};
+use boolean_paths::Expr as Function;
+
+use {eval, to_args, to_bool, Expression};
+use Expression::*;
+
+/// Evaluates an expression to a boolean function.
+///
+/// Returns an error if the result is not a boolean or a function of booleans.
+pub fn to_function(expr: &Expression, story: &[Expression]) -> Result<Function, String> {
+    fn convert(expr: &Expression) -> Result<Function, String> {
+        match *expr {
+            False => Ok(Function::False),
+            True => Ok(Function::True),
+            If(ref a, ref b) => Ok(Function::If(Arc::new(convert(a)?), Arc::new(convert(b)?))),
+            _ => Err(format!("Expected boolean function, found `{}`", expr)),
+        }
+    }
+    convert(&*eval(expr, story)?)
+}
+
+/// Returns arguments where `p(f(x)) != g(p(x0), ..., p(xn))`.
+///
+/// Returns `None` if the symmetric path `f[p] <=> g` holds for all arguments.
+pub fn sympath(
+    f: &Expression,
+    p: &Expression,
+    g: &Expression,
+    story: &[Expression]
+) -> Result<Option<Vec<bool>>, String> {
+    sat::sympath_counterexample(&to_function(f, story)?, &to_function(p, story)?,
+                                &to_function(g, story)?)
+}
+
+/// Decides a fact using the SAT solver.
+///
+/// Supports `a == b` of boolean functions, equal for all arguments,
+/// `a : [f] v`, `a : [(∃f)] v` and `check `x` is `b`` of these.
+/// Functions are curried, so `v` in `a : [f] v` and `a` in `a : [(∃f)] v`
+/// may be functions of the last arguments of `f`,
+/// e.g. `true : [and] id` and `id : [(∃and)] true` since `and(true) == id`.
+pub fn decide(fact: &Expression, story: &[Expression]) -> Result<bool, String> {
+    match *fact {
+        Check(ref x, ref b) => Ok(decide(x, story)? == to_bool(&*eval(b, story)?)?),
+        Eq(ref a, ref b) => {
+            let (a, b) = (to_function(a, story)?, to_function(b, story)?);
+            Ok(sat::difference(&a, &b)?.is_none())
+        }
+        IsType(ref a, ref ty) => {
+            let (f, v) = match **ty {
+                Path(ref f, ref v) => (f, v),
+                _ => return Err(format!("Expected path type in `{}`", fact)),
+            };
+            let a = eval(a, story)?;
+            match **f {
+                Ex(ref g) => {
+                    // `a` is in the range of `g` if `g(x, y) == a(y)` for some `x` and all `y`.
+                    let v = to_bool(&*eval(v, story)?)?;
+                    let g = to_function(g, story)?;
+                    let a = to_function(&a, story)?;
+                    let (n, k) = arities(&g, &a)?;
+                    let mut solver = Solver::new();
+                    let xs: Vec<Lit> = (0..n - k).map(|_| Lit::pos(solver.new_var())).collect();
+                    for i in 0..1 << k {
+                        let ys: Vec<bool> = (0..k).map(|j| (i >> j) & 1 == 1).collect();
+                        let mut args = xs.clone();
+                        args.extend(ys.iter().map(|&y| solver.constant(y)));
+                        let gx = solver.encode(&g, &args).unwrap();
+                        solver.add_clause(&[if a.eval(&ys) == Some(true) {gx} else {!gx}]);
+                    }
+                    Ok(solver.solve().is_some() == v)
+                }
+                _ => {
+                    // `f(a, y) == v(y)` for all `y`, where `a` is a tuple.
+                    let f = to_function(f, story)?;
+                    let v = to_function(v, story)?;
+                    let (n, k) = arities(&f, &v)?;
+                    let mut solver = Solver::new();
+                    let mut args: Vec<Lit> = to_args(&a, n - k)?.into_iter()
+                        .map(|x| solver.constant(x)).collect();
+                    let ys: Vec<Lit> = (0..k).map(|_| Lit::pos(solver.new_var())).collect();
+                    args.extend_from_slice(&ys);
+                    let fx = solver.encode(&f, &args).unwrap();
+                    let vy = solver.encode(&v, &ys).unwrap();
+                    solver.add_clause(&[fx, vy]);
+                    solver.add_clause(&[!fx, !vy]);
+                    Ok(solver.solve().is_none())
+                }
+            }
+        }
+        _ => Err(format!("Can not decide `{}`", fact)),
+    }
+}
+
+// Returns the arities of a function and of a function of its last arguments.
+fn arities(f: &Function, g: &Function) -> Result<(usize, usize), String> {
+    let n = f.arity().ok_or("Expected function with well defined arity")?;
+    let k = g.arity().ok_or("Expected function with well defined arity")?;
+    if k > n {
+        return Err(format!("Expected function with at most {} arguments", n));
+    }
+    Ok((n, k))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {parse, parse_lines, prove};
+
+    fn bool_defs() -> Vec<Expression> {
+        parse_lines("
+            false_1 := if(false, false)
+            not := if(false, true)
+            id := if(true, false)
+            true_1 := if(true, true)
+            and := if(id, false_1)
+            or := if(true_1, id)
+        ").unwrap()
+    }
+
+    #[test]
+    fn sympaths() {
+        let defs = bool_defs();
+        let name = |s: &str| parse(s).unwrap();
+        assert_eq!(sympath(&name("and"), &name("not"), &name("or"), &defs), Ok(None));
+        let x = sympath(&name("and"), &name("not"), &name("and"), &defs).unwrap().unwrap();
+        assert_ne!(x[0], x[1]);
+        assert_eq!(sympath(&name("not"), &name("not"), &name("not"), &defs), Ok(None));
+        assert!(sympath(&name("and"), &name("and"), &name("or"), &defs).is_err());
+        assert!(sympath(&name("a"), &name("not"), &name("or"), &defs).is_err());
+    }
+
+    #[test]
+    fn decide_facts() {
+        let mut defs = bool_defs();
+        defs.extend(parse_lines("
+            a := if(true, false)
+            b := false
+        ").unwrap());
+        let holds = |s: &str| decide(&parse(s).unwrap(), &defs);
+        assert_eq!(holds("and == if(if(true, false), false_1)"), Ok(true));
+        assert_eq!(holds("and == or"), Ok(false));
+        assert_eq!(holds("a : [and] false"), Ok(true));
+        assert_eq!(holds("b : [not] false"), Ok(false));
+        assert_eq!(holds("true : [and] id"), Ok(true));
+        assert_eq!(holds("false : [or] id"), Ok(true));
+        assert_eq!(holds("false : [or] true_1"), Ok(false));
+        assert_eq!(holds("false : [(∃and)] true"), Ok(true));
+        assert_eq!(holds("true : [(∃false_1)] true"), Ok(false));
+        assert_eq!(holds("true : [(∃false_1)] false"), Ok(true));
+        assert_eq!(holds("id : [(∃and)] true"), Ok(true));
+        assert_eq!(holds("not : [(∃and)] true"), Ok(false));
+        assert_eq!(holds("true_1 : [(∃or)] true"), Ok(true));
+        assert!(holds("and : [(∃not)] true").is_err());
+        assert_eq!(holds("check `b : [not] true` is `true`"), Ok(true));
+        assert!(holds("and == a(b)").is_err());
+        assert!(holds("a := b").is_err());
+    }
+
+    #[test]
+    fn agrees_with_proof() {
+        let mut start = bool_defs();
+        start.extend(parse_lines("
+            a := true
+            a : [and] id
+            b : [or] true
+        ").unwrap());
+        let goal = parse_lines("
+            check `a : [and] id` is `true`
+            check `id : [(∃and)] true` is `true`
+            check `true : [(∃or)] true` is `true`
+        ").unwrap();
+        let proof = prove(&start, &goal, &[]).unwrap();
+        let story = proof.facts();
+        for fact in &goal {
+            assert_eq!(decide(fact, &story), Ok(true));
+        }
+    }
+}
@@ -0,0 +1,210 @@
+//! Parses the syntax printed by `Display` for `Expression`, e.g. `a : [and] id`.
+//!
+//! The binary operators `:=`, `:` and `==` have the lowest precedence
+//! and take applications, paths or atoms on both sides.
+
+use std::sync::Arc;
+
+use Expression;
+use Expression::*;
+
+/// Parses an expression.
+pub fn parse(source: &str) -> Result<Expression, String> {
+    let mut parser = Parser {chars: source.chars().collect(), pos: 0};
+    let expr = parser.expr()?;
+    parser.skip_whitespace();
+    if parser.pos < parser.chars.len() {
+        return Err(format!("Unexpected `{}` at {}", parser.chars[parser.pos], parser.pos));
+    }
+    Ok(expr)
+}
+
+/// Parses one expression per line.
+///
+/// Empty lines and lines starting with `//` are ignored.
+pub fn parse_lines(source: &str) -> Result<Vec<Expression>, String> {
+    let mut res = vec![];
+    for (i, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("//") {continue};
+        res.push(parse(line).map_err(|err| format!("Line {}: {}", i + 1, err))?);
+    }
+    Ok(res)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.chars.len() && self.chars[self.pos].is_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn try_str(&mut self, s: &str) -> bool {
+        self.skip_whitespace();
+        let n = s.chars().count();
+        if self.pos + n <= self.chars.len() &&
+           self.chars[self.pos..self.pos + n].iter().cloned().eq(s.chars())
+        {
+            self.pos += n;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, s: &str) -> Result<(), String> {
+        if self.try_str(s) {
+            Ok(())
+        } else {
+            Err(format!("Expected `{}` at {}", s, self.pos))
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expression, String> {
+        let a = Box::new(self.term()?);
+        if self.try_str(":=") {
+            Ok(Define(a, Box::new(self.term()?)))
+        } else if self.try_str(":") {
+            Ok(IsType(a, Box::new(self.term()?)))
+        } else if self.try_str("==") {
+            Ok(Eq(a, Box::new(self.term()?)))
+        } else {
+            Ok(*a)
+        }
+    }
+
+    fn term(&mut self) -> Result<Expression, String> {
+        if self.try_str("[") {
+            let f = self.expr()?;
+            self.expect("]")?;
+            return Ok(Path(Box::new(f), Box::new(self.term()?)));
+        }
+        let mut a = self.atom()?;
+        while self.try_str("(") {
+            let v = self.expr()?;
+            self.expect(")")?;
+            a = Apply(Box::new(a), Box::new(v));
+        }
+        Ok(a)
+    }
+
+    fn atom(&mut self) -> Result<Expression, String> {
+        if self.try_str("(") {
+            let ex = self.try_str("∃");
+            let a = self.expr()?;
+            self.expect(")")?;
+            return Ok(if ex {Ex(Box::new(a))} else {a});
+        }
+        self.skip_whitespace();
+        let start = self.pos;
+        while self.pos < self.chars.len() &&
+              (self.chars[self.pos].is_alphanumeric() || self.chars[self.pos] == '_')
+        {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return Err(format!("Expected expression at {}", self.pos));
+        }
+        let name: String = self.chars[start..self.pos].iter().collect();
+        match &*name {
+            "false" => Ok(False),
+            "true" => Ok(True),
+            "if" => {
+                self.expect("(")?;
+                let a = self.expr()?;
+                self.expect(",")?;
+                let b = self.expr()?;
+                self.expect(")")?;
+                Ok(If(Box::new(a), Box::new(b)))
+            }
+            "check" => {
+                self.expect("`")?;
+                let a = self.expr()?;
+                self.expect("`")?;
+                self.expect("is")?;
+                self.expect("`")?;
+                let b = self.expr()?;
+                self.expect("`")?;
+                Ok(Check(Box::new(a), Box::new(b)))
+            }
+            _ => Ok(Variable(Arc::new(name))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use *;
+
+    #[test]
+    fn parse_expressions() {
+        assert_eq!(parse("a : [and] id"), Ok(IsType(var("a"), path(var("and"), var("id")))));
+        assert_eq!(parse("not := if(false, true)"), Ok(Define(var("not"), if_(false_(), true_()))));
+        assert_eq!(parse("(∃and)(id) == true"),
+                   Ok(Eq(apply(ex(var("and")), var("id")), true_())));
+        assert_eq!(parse("check `id : [(∃and)] true` is `true`"),
+                   Ok(Check(is_type(var("id"), path(ex(var("and")), true_())), true_())));
+        assert_eq!(parse("f(a)(b)"), Ok(Apply(apply(var("f"), var("a")), var("b"))));
+        assert!(parse("a : ").is_err());
+        assert!(parse("if(a)").is_err());
+        assert!(parse("a b").is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        let exprs = vec![
+            Define(var("and"), if_(var("id"), var("false_1"))),
+            IsType(var("b"), path(var("and"), true_())),
+            Check(is_type(true_(), path(ex(var("true_1")), true_())), true_()),
+            Check(is_type(var("a"), path(var("and"), var("id"))), eq(var("x"), true_())),
+            Eq(apply(if_(true_(), false_()), apply(var("f"), false_())), false_()),
+        ];
+        for expr in exprs {
+            assert_eq!(parse(&expr.to_string()), Ok(expr));
+        }
+    }
+
+    #[test]
+    fn round_trip_nested() {
+        // All expressions with constructors nested up to two levels.
+        let mut exprs = vec![False, Variable(Arc::new("a".into()))];
+        for _ in 0..2 {
+            let mut next = exprs.clone();
+            for a in &exprs {
+                next.push(Ex(Box::new(a.clone())));
+                for b in &exprs {
+                    let (a, b) = (Box::new(a.clone()), Box::new(b.clone()));
+                    next.push(If(a.clone(), b.clone()));
+                    next.push(Path(a.clone(), b.clone()));
+                    next.push(IsType(a.clone(), b.clone()));
+                    next.push(Define(a.clone(), b.clone()));
+                    next.push(Apply(a.clone(), b.clone()));
+                    next.push(Check(a.clone(), b.clone()));
+                    next.push(Eq(a, b));
+                }
+            }
+            exprs = next;
+        }
+        for expr in exprs {
+            assert_eq!(parse(&expr.to_string()), Ok(expr));
+        }
+    }
+
+    #[test]
+    fn lines() {
+        let facts = parse_lines("
+            // Functions.
+            id := if(true, false)
+
+            a : [id] true
+        ").unwrap();
+        assert_eq!(facts.len(), 2);
+        assert_eq!(parse_lines("a :\nb").unwrap_err(), "Line 1: Expected expression at 3");
+    }
+}
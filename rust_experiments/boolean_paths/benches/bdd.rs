@@ -0,0 +1,79 @@
+//! Compares the tree form of `Expr` with the BDD backend.
+//!
+//! Run with `cargo bench --features bdd`.
+
+#[macro_use]
+extern crate bencher;
+extern crate boolean_paths;
+
+use bencher::Bencher;
+use boolean_paths::Expr;
+use boolean_paths::bdd::{Bdd, Node, FALSE};
+
+const N: usize = 10;
+
+fn parity() -> Expr {
+    Expr::from_fn(N, |args| args.iter().filter(|&&x| x).count() % 2 == 1)
+}
+
+fn and() -> Expr {
+    Expr::from_fn(N, |args| args.iter().all(|&x| x))
+}
+
+fn tree_sympath_not_and(b: &mut Bencher) {
+    let f = and();
+    b.iter(|| f.sympath(&Expr::not()));
+}
+
+fn bdd_sympath_not_and(b: &mut Bencher) {
+    let f = and();
+    b.iter(|| {
+        let mut bdd = Bdd::new();
+        let f = bdd.from_expr(&f);
+        let not = bdd.from_expr(&Expr::not());
+        bdd.sympath(f, N, not)
+    });
+}
+
+fn tree_asympath_parity(b: &mut Bencher) {
+    let f = parity();
+    let mut ps = vec![Expr::not(); N];
+    ps.push(Expr::id());
+    b.iter(|| f.find_asympath(&ps));
+}
+
+fn bdd_asympath_parity(b: &mut Bencher) {
+    let f = parity();
+    b.iter(|| {
+        let mut bdd = Bdd::new();
+        let f = bdd.from_expr(&f);
+        let not = bdd.from_expr(&Expr::not());
+        let id = bdd.from_expr(&Expr::id());
+        bdd.find_asympath(f, N, &[(not, 1); N], id)
+    });
+}
+
+/// Builds parity directly, which is not possible in tree form for many arguments.
+fn bdd_asympath_parity_64(b: &mut Bencher) {
+    let n = 64;
+    b.iter(|| {
+        let mut bdd = Bdd::new();
+        let mut f: Node = FALSE;
+        for i in (0..n).rev() {
+            let x = bdd.var(i);
+            f = bdd.xor(x, f);
+        }
+        let not = bdd.from_expr(&Expr::not());
+        let id = bdd.from_expr(&Expr::id());
+        bdd.find_asympath(f, n, &vec![(not, 1); n], id)
+    });
+}
+
+benchmark_group!(benches,
+    tree_sympath_not_and,
+    bdd_sympath_not_and,
+    tree_asympath_parity,
+    bdd_asympath_parity,
+    bdd_asympath_parity_64
+);
+benchmark_main!(benches);
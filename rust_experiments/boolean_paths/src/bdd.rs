@@ -0,0 +1,523 @@
+//! Reduced ordered binary decision diagrams.
+//!
+//! An alternative to `Expr` for functions with many arguments.
+//! Nodes are stored in a unique table, so equal functions get the same node.
+//! Variable `0` is the first argument, at the top of the diagram.
+//!
+//! Requires the `bdd` feature.
+
+use std::collections::{HashMap, HashSet};
+
+use {Expr, NoPath};
+
+/// A node in a diagram.
+pub type Node = usize;
+
+/// The node of `false`.
+pub const FALSE: Node = 0;
+/// The node of `true`.
+pub const TRUE: Node = 1;
+
+/// The variable of terminal nodes, below all other variables.
+const TERMINAL: usize = usize::MAX;
+
+// Binary operations as truth tables, `op(a, b)` is bit `2 * a + b`.
+const AND: u8 = 0b1000;
+const OR: u8 = 0b1110;
+const XOR: u8 = 0b0110;
+const EQ: u8 = 0b1001;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+struct NodeData {
+    var: usize,
+    hi: Node,
+    lo: Node,
+}
+
+/// Stores nodes of reduced ordered binary decision diagrams.
+pub struct Bdd {
+    nodes: Vec<NodeData>,
+    unique: HashMap<NodeData, Node>,
+    apply_cache: HashMap<(u8, Node, Node), Node>,
+}
+
+impl Default for Bdd {
+    fn default() -> Bdd {
+        Bdd::new()
+    }
+}
+
+impl Bdd {
+    /// Creates a new diagram with the nodes `false` and `true`.
+    pub fn new() -> Bdd {
+        Bdd {
+            nodes: vec![
+                NodeData {var: TERMINAL, hi: FALSE, lo: FALSE},
+                NodeData {var: TERMINAL, hi: TRUE, lo: TRUE},
+            ],
+            unique: HashMap::new(),
+            apply_cache: HashMap::new(),
+        }
+    }
+
+    /// Returns the node of a constant.
+    pub fn constant(val: bool) -> Node {
+        if val {TRUE} else {FALSE}
+    }
+
+    /// Returns the number of nodes in the unique table.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns `true` if there are only the nodes `false` and `true`.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.len() == 2
+    }
+
+    /// Returns the node testing variable `var`, with sub nodes for `true` and `false`.
+    ///
+    /// When both sub nodes are equal, the variable is irrelevant and the sub node is returned.
+    pub fn mk(&mut self, var: usize, hi: Node, lo: Node) -> Node {
+        if hi == lo {return hi};
+        let data = NodeData {var, hi, lo};
+        if let Some(&node) = self.unique.get(&data) {return node};
+        let node = self.nodes.len();
+        self.nodes.push(data);
+        self.unique.insert(data, node);
+        node
+    }
+
+    /// Returns the function that returns argument `var`.
+    pub fn var(&mut self, var: usize) -> Node {
+        self.mk(var, TRUE, FALSE)
+    }
+
+    fn top(&self, f: Node) -> usize {
+        self.nodes[f].var
+    }
+
+    // Returns the sub nodes when `var` is `true` and `false`.
+    fn cofactors(&self, f: Node, var: usize) -> (Node, Node) {
+        let data = self.nodes[f];
+        if data.var == var {(data.hi, data.lo)} else {(f, f)}
+    }
+
+    fn apply(&mut self, op: u8, f: Node, g: Node) -> Node {
+        if f <= TRUE && g <= TRUE {
+            return Bdd::constant((op >> (2 * f + g)) & 1 == 1);
+        }
+        if let Some(&res) = self.apply_cache.get(&(op, f, g)) {return res};
+        let var = self.top(f).min(self.top(g));
+        let (f1, f0) = self.cofactors(f, var);
+        let (g1, g0) = self.cofactors(g, var);
+        let hi = self.apply(op, f1, g1);
+        let lo = self.apply(op, f0, g0);
+        let res = self.mk(var, hi, lo);
+        self.apply_cache.insert((op, f, g), res);
+        res
+    }
+
+    /// Returns `!f`.
+    pub fn not(&mut self, f: Node) -> Node {
+        self.apply(XOR, f, TRUE)
+    }
+
+    /// Returns `f & g`.
+    pub fn and(&mut self, f: Node, g: Node) -> Node {
+        self.apply(AND, f, g)
+    }
+
+    /// Returns `f | g`.
+    pub fn or(&mut self, f: Node, g: Node) -> Node {
+        self.apply(OR, f, g)
+    }
+
+    /// Returns `f ^ g`.
+    pub fn xor(&mut self, f: Node, g: Node) -> Node {
+        self.apply(XOR, f, g)
+    }
+
+    /// Returns `f == g`.
+    pub fn eq(&mut self, f: Node, g: Node) -> Node {
+        self.apply(EQ, f, g)
+    }
+
+    /// Returns `if c {a} else {b}`.
+    pub fn ite(&mut self, c: Node, a: Node, b: Node) -> Node {
+        let ca = self.and(c, a);
+        let not_c = self.not(c);
+        let cb = self.and(not_c, b);
+        self.or(ca, cb)
+    }
+
+    /// Restricts variable `var` to a value.
+    pub fn restrict(&mut self, f: Node, var: usize, val: bool) -> Node {
+        fn restrict(
+            bdd: &mut Bdd,
+            f: Node,
+            var: usize,
+            val: bool,
+            cache: &mut HashMap<Node, Node>
+        ) -> Node {
+            let data = bdd.nodes[f];
+            if data.var == TERMINAL || data.var > var {return f};
+            if data.var == var {return if val {data.hi} else {data.lo}};
+            if let Some(&res) = cache.get(&f) {return res};
+            let hi = restrict(bdd, data.hi, var, val, cache);
+            let lo = restrict(bdd, data.lo, var, val, cache);
+            let res = bdd.mk(data.var, hi, lo);
+            cache.insert(f, res);
+            res
+        }
+        restrict(self, f, var, val, &mut HashMap::new())
+    }
+
+    /// Returns `true` if `f` is true for some value of `var`.
+    pub fn exists(&mut self, f: Node, var: usize) -> Node {
+        let hi = self.restrict(f, var, true);
+        let lo = self.restrict(f, var, false);
+        self.or(hi, lo)
+    }
+
+    /// Renames variables, where variable `i` becomes `map[i]`.
+    ///
+    /// The map must preserve the order of variables.
+    pub fn rename(&mut self, f: Node, map: &[usize]) -> Node {
+        fn rename(
+            bdd: &mut Bdd,
+            f: Node,
+            map: &[usize],
+            cache: &mut HashMap<Node, Node>
+        ) -> Node {
+            if f <= TRUE {return f};
+            if let Some(&res) = cache.get(&f) {return res};
+            let data = bdd.nodes[f];
+            let hi = rename(bdd, data.hi, map, cache);
+            let lo = rename(bdd, data.lo, map, cache);
+            let res = bdd.mk(map[data.var], hi, lo);
+            cache.insert(f, res);
+            res
+        }
+        rename(self, f, map, &mut HashMap::new())
+    }
+
+    /// Evaluates with values of variables.
+    pub fn eval(&self, mut f: Node, args: &[bool]) -> bool {
+        while f > TRUE {
+            let data = self.nodes[f];
+            f = if args[data.var] {data.hi} else {data.lo};
+        }
+        f == TRUE
+    }
+
+    /// Returns values of the first `n` variables for which `f` is true.
+    pub fn any_sat(&self, mut f: Node, n: usize) -> Option<Vec<bool>> {
+        if f == FALSE {return None};
+        let mut res = vec![false; n];
+        while f > TRUE {
+            let data = self.nodes[f];
+            if data.lo != FALSE {
+                f = data.lo;
+            } else {
+                res[data.var] = true;
+                f = data.hi;
+            }
+        }
+        Some(res)
+    }
+
+    /// Returns the number of nodes reachable from `f`, including terminals.
+    pub fn size(&self, f: Node) -> usize {
+        let mut visited = HashSet::new();
+        visited.insert(f);
+        let mut stack = vec![f];
+        while let Some(node) = stack.pop() {
+            if node > TRUE {
+                let data = self.nodes[node];
+                for &sub in &[data.hi, data.lo] {
+                    if visited.insert(sub) {stack.push(sub)};
+                }
+            }
+        }
+        visited.len()
+    }
+
+    /// Returns the node of a function.
+    pub fn from_expr(&mut self, expr: &Expr) -> Node {
+        fn from_expr(
+            bdd: &mut Bdd,
+            expr: &Expr,
+            var: usize,
+            cache: &mut HashMap<(*const Expr, usize), Node>
+        ) -> Node {
+            match *expr {
+                Expr::False => FALSE,
+                Expr::True => TRUE,
+                Expr::If(ref a, ref b) => {
+                    let key = (expr as *const Expr, var);
+                    if let Some(&res) = cache.get(&key) {return res};
+                    let hi = from_expr(bdd, a, var + 1, cache);
+                    let lo = from_expr(bdd, b, var + 1, cache);
+                    let res = bdd.mk(var, hi, lo);
+                    cache.insert(key, res);
+                    res
+                }
+            }
+        }
+        from_expr(self, expr, 0, &mut HashMap::new())
+    }
+
+    /// Returns the function of a node with `n` arguments.
+    pub fn to_expr(&self, f: Node, n: usize) -> Expr {
+        fn to_expr(bdd: &Bdd, f: Node, var: usize, n: usize) -> Expr {
+            if var == n {
+                return if f == TRUE {Expr::True} else {Expr::False};
+            }
+            let (hi, lo) = bdd.cofactors(f, var);
+            Expr::if_(to_expr(bdd, hi, var + 1, n), to_expr(bdd, lo, var + 1, n))
+        }
+        to_expr(self, f, 0, n)
+    }
+
+    /// Finds the asymmetric path `f[p0 x ... -> q]`, like `Expr::find_asympath`.
+    ///
+    /// The function `f` has `n` arguments.
+    /// Each path is given with its number of arguments.
+    /// The path `q` of the result must be unary.
+    pub fn find_asympath(
+        &mut self,
+        f: Node,
+        n: usize,
+        ps: &[(Node, usize)],
+        q: Node
+    ) -> Result<Node, NoPath> {
+        if ps.iter().map(|p| p.1).sum::<usize>() != n {return Err(NoPath::Arity)};
+        let unary = q <= TRUE ||
+            self.top(q) == 0 && self.nodes[q].hi <= TRUE && self.nodes[q].lo <= TRUE;
+        if !unary {return Err(NoPath::NotUnary)};
+        let m = ps.len();
+        // Each argument of `g` is followed by the arguments of its path,
+        // which keeps the diagrams small.
+        let mut pos_y = vec![];
+        let mut pos_x = vec![];
+        for &(_, k) in ps {
+            pos_y.push(pos_y.len() + pos_x.len());
+            for _ in 0..k {
+                pos_x.push(pos_y.len() + pos_x.len());
+            }
+        }
+        let total = m + n;
+
+        let fx = self.rename(f, &pos_x);
+        let q1 = self.restrict(q, 0, true);
+        let q0 = self.restrict(q, 0, false);
+        let qf = self.ite(fx, q1, q0);
+        let mut ys = TRUE;
+        let mut start = 0;
+        for (i, &(p, k)) in ps.iter().enumerate() {
+            let px = self.rename(p, &pos_x[start..start + k]);
+            let y = self.var(pos_y[i]);
+            let y_px = self.eq(y, px);
+            ys = self.and(ys, y_px);
+            start += k;
+        }
+        let not_qf = self.not(qf);
+        let mut hi = self.and(ys, qf);
+        let mut lo = self.and(ys, not_qf);
+        for &var in &pos_x {
+            hi = self.exists(hi, var);
+            lo = self.exists(lo, var);
+        }
+
+        let conflict = self.and(hi, lo);
+        if let Some(vals) = self.any_sat(conflict, total) {
+            // Find arguments of `f` with the same paths but different results.
+            let mut ys_y = ys;
+            for &var in &pos_y {
+                ys_y = self.restrict(ys_y, var, vals[var]);
+            }
+            let a = self.and(ys_y, not_qf);
+            let b = self.and(ys_y, qf);
+            let a = self.any_sat(a, total).unwrap();
+            let b = self.any_sat(b, total).unwrap();
+            return Err(NoPath::Conflict(
+                pos_x.iter().map(|&var| a[var]).collect(),
+                pos_x.iter().map(|&var| b[var]).collect(),
+            ));
+        }
+
+        let mut map = vec![0; total];
+        for (i, &var) in pos_y.iter().enumerate() {
+            map[var] = i;
+        }
+        let mut g = self.rename(hi, &map);
+        // When a path is constant, the argument is made irrelevant.
+        for (i, &(p, _)) in ps.iter().enumerate() {
+            if p <= TRUE {
+                g = self.restrict(g, i, p == TRUE);
+            }
+        }
+        Ok(g)
+    }
+
+    /// Returns `true` if the asymmetric path `f[p0 x ... -> q]` exists.
+    pub fn has_asympath(&mut self, f: Node, n: usize, ps: &[(Node, usize)], q: Node) -> bool {
+        self.find_asympath(f, n, ps, q).is_ok()
+    }
+
+    /// Creates the symmetric path `f[p]` by a unary function, like `Expr::sympath`.
+    pub fn sympath(&mut self, f: Node, n: usize, p: Node) -> Option<Node> {
+        self.find_asympath(f, n, &vec![(p, 1); n], p).ok()
+    }
+}
+
+impl Expr {
+    /// Finds the asymmetric path `f[p0 -> ... -> pn]` using a diagram,
+    /// like `Expr::find_asympath`.
+    pub fn find_asympath_bdd(&self, ps: &[Expr]) -> Result<Expr, NoPath> {
+        let n = self.arity().ok_or(NoPath::Arity)?;
+        let (q, ps) = ps.split_last().ok_or(NoPath::Arity)?;
+        if q.arity() != Some(1) {return Err(NoPath::NotUnary)};
+        let mut bdd = Bdd::new();
+        let f = bdd.from_expr(self);
+        let ps = ps.iter().map(|p| p.arity().map(|k| (bdd.from_expr(p), k)))
+            .collect::<Option<Vec<_>>>().ok_or(NoPath::Arity)?;
+        let q = bdd.from_expr(q);
+        let g = bdd.find_asympath(f, n, &ps, q)?;
+        Ok(bdd.to_expr(g, ps.len()))
+    }
+
+    /// Returns `true` if the asymmetric path `f[p0 -> ... -> pn]` exists,
+    /// like `Expr::has_asympath`.
+    ///
+    /// The tension is only tracked by the tree form,
+    /// so a call with tension is decided by `Expr::has_asympath`.
+    pub fn has_asympath_bdd(&self, ps: &[Expr], tension: bool) -> bool {
+        if tension {return self.has_asympath(ps, tension)};
+        self.find_asympath_bdd(ps).is_ok()
+    }
+
+    /// Creates the symmetric path `f[p]` by a unary function using a diagram,
+    /// like `Expr::sympath`.
+    pub fn sympath_bdd(&self, p: &Expr) -> Option<Expr> {
+        let n = self.arity()?;
+        self.find_asympath_bdd(&vec![p.clone(); n + 1]).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reduced() {
+        let mut bdd = Bdd::new();
+        let f = Expr::parse("a & b | a & !b").unwrap();
+        let f = bdd.from_expr(&f);
+        let a = bdd.var(0);
+        assert_eq!(f, a);
+        let not_a = bdd.not(a);
+        assert_eq!(bdd.and(a, not_a), FALSE);
+        assert_eq!(bdd.or(a, not_a), TRUE);
+        assert_eq!(bdd.size(a), 3);
+    }
+
+    #[test]
+    fn expr_round_trip() {
+        let mut bdd = Bdd::new();
+        for n in 0..4 {
+            for f in Expr::all(n) {
+                let node = bdd.from_expr(&f);
+                assert_eq!(bdd.to_expr(node, n), f);
+                for i in 0..1 << n {
+                    let args: Vec<bool> = (0..n).map(|k| (i >> k) & 1 == 1).collect();
+                    assert_eq!(Some(bdd.eval(node, &args)), f.eval(&args));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn same_as_expr() {
+        let mut bdd = Bdd::new();
+        let unary: Vec<(Expr, Node)> = Expr::unary().into_iter()
+            .map(|p| {let node = bdd.from_expr(&p); (p, node)}).collect();
+        for entry in Expr::asympath_table(2) {
+            let n = entry.ps.len() - 1;
+            let f = bdd.from_expr(&entry.f);
+            let ps: Vec<(Node, usize)> = entry.ps[..n].iter()
+                .map(|p| (unary.iter().find(|u| &u.0 == p).unwrap().1, 1)).collect();
+            let q = bdd.from_expr(&entry.ps[n]);
+            match (bdd.find_asympath(f, n, &ps, q), entry.g) {
+                (Ok(g), Ok(expected)) => assert_eq!(bdd.to_expr(g, n), expected),
+                (Err(_), Err(_)) => {}
+                (x, y) => panic!("{:?} {:?} {:?}", entry.f, x, y),
+            }
+        }
+    }
+
+    #[test]
+    fn expr_entry_points() {
+        for entry in Expr::asympath_table(2) {
+            let g = entry.f.find_asympath_bdd(&entry.ps);
+            assert_eq!(g.is_ok(), entry.g.is_ok());
+            if let Ok(g) = g {
+                assert_eq!(Ok(g), entry.g);
+            }
+            assert_eq!(entry.f.has_asympath_bdd(&entry.ps, false), entry.g.is_ok());
+        }
+        for n in 0..3 {
+            for f in Expr::all(n) {
+                for p in Expr::unary() {
+                    assert_eq!(f.sympath_bdd(&p), f.sympath(&p));
+                }
+            }
+        }
+        let f = Expr::parse("a & b | c & d").unwrap();
+        assert_eq!(f.find_asympath_bdd(&[Expr::and(), Expr::and(), Expr::id()]), Ok(Expr::or()));
+        assert_eq!(f.find_asympath_bdd(&[Expr::and(), Expr::id()]), Err(NoPath::Arity));
+    }
+
+    #[test]
+    fn binary_paths() {
+        let mut bdd = Bdd::new();
+        let f = bdd.from_expr(&Expr::parse("a & b | c & d").unwrap());
+        let and = bdd.from_expr(&Expr::and());
+        let or = bdd.from_expr(&Expr::or());
+        let id = bdd.from_expr(&Expr::id());
+        assert_eq!(bdd.find_asympath(f, 4, &[(and, 2), (and, 2)], id), Ok(or));
+        match bdd.find_asympath(f, 4, &[(or, 2), (or, 2)], id) {
+            Err(NoPath::Conflict(a, b)) => {
+                let eval = |args: &[bool]| bdd.eval(f, args);
+                assert!(!eval(&a) && eval(&b));
+                assert_eq!((a[0] || a[1], a[2] || a[3]), (b[0] || b[1], b[2] || b[3]));
+            }
+            x => panic!("Expected conflict, found `{:?}`", x),
+        }
+        assert_eq!(bdd.find_asympath(f, 4, &[(and, 2)], id), Err(NoPath::Arity));
+        assert_eq!(bdd.find_asympath(f, 4, &[(and, 2), (and, 2)], and), Err(NoPath::NotUnary));
+    }
+
+    #[test]
+    fn many_arguments() {
+        let n = 64;
+        let mut bdd = Bdd::new();
+        let not = bdd.from_expr(&Expr::not());
+        let id = bdd.from_expr(&Expr::id());
+        let mut and = TRUE;
+        let mut or = FALSE;
+        let mut parity = FALSE;
+        for i in (0..n).rev() {
+            let x = bdd.var(i);
+            and = bdd.and(x, and);
+            or = bdd.or(x, or);
+            parity = bdd.xor(x, parity);
+        }
+        // and[not] <=> or
+        assert_eq!(bdd.sympath(and, n, not), Some(or));
+        // parity[not x ... x not -> id] <=> parity, for even number of arguments.
+        assert_eq!(bdd.find_asympath(parity, n, &vec![(not, 1); n], id), Ok(parity));
+        assert_eq!(bdd.size(parity), 2 * n + 1);
+    }
+}
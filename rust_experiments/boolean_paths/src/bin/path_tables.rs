@@ -0,0 +1,52 @@
+//! Generates tables of boolean paths.
+//!
+//! Usage: `path_tables [max_arity] [md|csv] [sym|asym|ex|all]`
+//!
+//! The default is `path_tables 2 md all`.
+
+extern crate boolean_paths;
+
+use std::env;
+use std::process;
+
+use boolean_paths::tables::{self, Table};
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let max_arity: usize = match args.first().map(|s| s.parse()) {
+        None => 2,
+        Some(Ok(n)) if n <= 3 => n,
+        Some(_) => usage("Expected max arity from 0 to 3"),
+    };
+    let format = args.get(1).map(|s| &**s).unwrap_or("md");
+    if format != "md" && format != "csv" {
+        usage("Expected format `md` or `csv`");
+    }
+    let kind = args.get(2).map(|s| &**s).unwrap_or("all");
+    let tables: Vec<(&str, Table)> = match kind {
+        "sym" => vec![("Symmetric paths", tables::sympath_table(max_arity))],
+        "asym" => vec![("Asymmetric paths", tables::asympath_table(max_arity))],
+        "ex" => vec![("Existential paths", tables::existential_table(max_arity))],
+        "all" => vec![
+            ("Symmetric paths", tables::sympath_table(max_arity)),
+            ("Asymmetric paths", tables::asympath_table(max_arity)),
+            ("Existential paths", tables::existential_table(max_arity)),
+        ],
+        _ => usage("Expected table `sym`, `asym`, `ex` or `all`"),
+    };
+    for (i, (title, table)) in tables.iter().enumerate() {
+        if i > 0 {println!()};
+        if format == "md" {
+            println!("## {}\n", title);
+            print!("{}", table.to_markdown());
+        } else {
+            print!("{}", table.to_csv());
+        }
+    }
+}
+
+fn usage(msg: &str) -> ! {
+    eprintln!("{}", msg);
+    eprintln!("Usage: path_tables [max_arity] [md|csv] [sym|asym|ex|all]");
+    process::exit(1);
+}
@@ -1,5 +1,34 @@
 use std::sync::Arc;
 
+#[cfg(feature = "bdd")]
+pub mod bdd;
+pub mod post;
+pub mod sat;
+mod syntax;
+pub mod tables;
+
+/// The reason why an asymmetric path does not exist.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum NoPath {
+    /// The arguments of paths do not add up to the arguments of the function.
+    Arity,
+    /// The path of the result is not a unary function.
+    NotUnary,
+    /// Two argument vectors have equal paths but different paths of the result.
+    Conflict(Vec<bool>, Vec<bool>),
+}
+
+/// Stores an asymmetric path `f[p0 -> ... -> pn]` in a table.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct AsymPathEntry {
+    /// The function.
+    pub f: Expr,
+    /// The paths of arguments followed by the path of the result.
+    pub ps: Vec<Expr>,
+    /// The function that the path leads to.
+    pub g: Result<Expr, NoPath>,
+}
+
 /// Stores boolean function.
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub enum Expr {
@@ -54,6 +83,108 @@ impl Expr {
         }
     }
 
+    /// Creates a function with `n` arguments from a function on argument vectors.
+    pub fn from_fn<F: Fn(&[bool]) -> bool>(n: usize, f: F) -> Expr {
+        fn build<F: Fn(&[bool]) -> bool>(n: usize, args: &mut Vec<bool>, f: &F) -> Expr {
+            if args.len() == n {
+                return if f(args) {Expr::True} else {Expr::False};
+            }
+            args.push(true);
+            let a = build(n, args, f);
+            args.pop();
+            args.push(false);
+            let b = build(n, args, f);
+            args.pop();
+            Expr::if_(a, b)
+        }
+        build(n, &mut vec![], &f)
+    }
+
+    /// Creates a function from a truth table.
+    ///
+    /// The rows start with all arguments `false` and end with all `true`,
+    /// where the first argument is the most significant bit,
+    /// e.g. `and` is `[false, false, false, true]`.
+    /// Returns `None` if the length is not a power of two.
+    pub fn from_truth_table(table: &[bool]) -> Option<Expr> {
+        if !table.len().is_power_of_two() {return None};
+        let n = table.len().trailing_zeros() as usize;
+        Some(Expr::from_fn(n, |args| table[row(args)]))
+    }
+
+    /// Returns the truth table, in the same order as `from_truth_table`.
+    ///
+    /// Returns `None` if the arity is not well defined.
+    pub fn truth_table(&self) -> Option<Vec<bool>> {
+        let n = self.arity()?;
+        (0..1 << n).map(|i| self.eval(&row_args(i, n))).collect()
+    }
+
+    /// Parses a function from an expression, e.g. `a & !b`.
+    ///
+    /// The arguments are the variables in the order they appear.
+    /// Operators are `!`, `&`, `^` and `|`, from highest to lowest precedence.
+    /// The constants are `0` and `1`.
+    pub fn parse(source: &str) -> Result<Expr, String> {
+        let mut vars = vec![];
+        let ast = syntax::parse(source, &mut vars, false)?;
+        Ok(Expr::from_fn(vars.len(), |args| ast.eval(args)))
+    }
+
+    /// Parses a function from an expression with the given argument names.
+    pub fn parse_with(args: &[&str], source: &str) -> Result<Expr, String> {
+        let mut vars: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+        let ast = syntax::parse(source, &mut vars, true)?;
+        Ok(Expr::from_fn(vars.len(), |args| ast.eval(args)))
+    }
+
+    /// Creates a function from sub functions for the first argument `true` and `false`.
+    ///
+    /// Equal sub functions are shared.
+    pub fn if_(a: Expr, b: Expr) -> Expr {
+        if a == b {
+            let sub = Arc::new(a);
+            Expr::If(sub.clone(), sub)
+        } else {
+            Expr::If(Arc::new(a), Arc::new(b))
+        }
+    }
+
+    /// Returns the number of arguments.
+    ///
+    /// Returns `None` if sub functions have different number of arguments.
+    pub fn arity(&self) -> Option<usize> {
+        match *self {
+            Expr::False | Expr::True => Some(0),
+            Expr::If(ref a, ref b) => {
+                let n = a.arity()?;
+                if b.arity()? == n {Some(n + 1)} else {None}
+            }
+        }
+    }
+
+    /// Evaluates the function with all arguments.
+    ///
+    /// Returns `None` if the number of arguments does not match.
+    pub fn eval(&self, args: &[bool]) -> Option<bool> {
+        match *self {
+            Expr::False if args.is_empty() => Some(false),
+            Expr::True if args.is_empty() => Some(true),
+            Expr::If(..) if !args.is_empty() => self.apply(args[0])?.eval(&args[1..]),
+            _ => None,
+        }
+    }
+
+    /// Reduces to canonical form.
+    ///
+    /// Irrelevant arguments are merged into one shared sub function.
+    pub fn reduce(&self) -> Expr {
+        match *self {
+            Expr::False | Expr::True => self.clone(),
+            Expr::If(ref a, ref b) => Expr::if_(a.reduce(), b.reduce()),
+        }
+    }
+
     /// Creates the symmetric path by `not`.
     pub fn sympath_not(&self) -> Expr {
         match *self {
@@ -70,6 +201,16 @@ impl Expr {
         }
     }
 
+    /// Creates the symmetric path `f[p]` by a unary function.
+    ///
+    /// This is the asymmetric path `f[p -> ... -> p]`.
+    /// Returns `None` if the path does not exist,
+    /// which for boolean functions only happens when `p` is not unary.
+    pub fn sympath(&self, p: &Expr) -> Option<Expr> {
+        let n = self.arity()?;
+        self.find_asympath(&vec![p.clone(); n + 1]).ok()
+    }
+
     /// Returns `true` if argument is irrelevant.
     ///
     /// This is the case when the two sub expressions are equal.
@@ -98,7 +239,110 @@ impl Expr {
         self == &Expr::not() || self == &Expr::id()
     }
 
+    /// Returns the unary functions `false_1`, `not`, `id` and `true_1`.
+    pub fn unary() -> Vec<Expr> {
+        vec![Expr::false_1(), Expr::not(), Expr::id(), Expr::true_1()]
+    }
+
+    /// Returns all functions with `n` arguments, ordered by truth table.
+    pub fn all(n: usize) -> Vec<Expr> {
+        let rows = 1 << n;
+        (0..1usize << rows).map(|t| {
+            Expr::from_fn(n, |args| (t >> (rows - 1 - row(args))) & 1 == 1)
+        }).collect()
+    }
+
+    /// Finds the asymmetric path `f[p0 -> ... -> pn]`.
+    ///
+    /// This is the function `g` such that `pn(f(x0, ...)) == g(p0(x0), ...)`.
+    /// A path with more than one argument takes the next arguments of `f`,
+    /// e.g. `f[and x and -> id]` for a function `f` with 4 arguments
+    /// is `g` such that `f(x0, x1, x2, x3) == g(and(x0, x1), and(x2, x3))`.
+    /// When `pi` is constant, `g` only gets one value for the argument,
+    /// so the argument is made irrelevant.
+    pub fn find_asympath(&self, ps: &[Expr]) -> Result<Expr, NoPath> {
+        let n = self.arity().ok_or(NoPath::Arity)?;
+        let (q, ps) = ps.split_last().ok_or(NoPath::Arity)?;
+        if q.arity() != Some(1) {return Err(NoPath::NotUnary)};
+        let arities: Vec<usize> = ps.iter().map(|p| p.arity())
+            .collect::<Option<_>>().ok_or(NoPath::Arity)?;
+        if arities.iter().sum::<usize>() != n {return Err(NoPath::Arity)};
+        let m = ps.len();
+        let paths = |args: &[bool]| -> Vec<bool> {
+            let mut start = 0;
+            let mut ys = Vec::with_capacity(m);
+            for (p, &k) in ps.iter().zip(&arities) {
+                ys.push(p.eval(&args[start..start + k]).unwrap());
+                start += k;
+            }
+            ys
+        };
+
+        // Stores the result of `g` with the row of `f` that produced it.
+        let mut table: Vec<Option<(bool, usize)>> = vec![None; 1 << m];
+        for i in 0..1 << n {
+            let args = row_args(i, n);
+            let ys = paths(&args);
+            let res = q.eval(&[self.eval(&args).unwrap()]).unwrap();
+            match table[row(&ys)] {
+                Some((val, j)) if val != res => {
+                    return Err(NoPath::Conflict(row_args(j, n), args));
+                }
+                Some(_) => {}
+                None => table[row(&ys)] = Some((res, i)),
+            }
+        }
+        let constants: Vec<Option<bool>> = ps.iter().map(|p| p.constant()).collect();
+        Ok(Expr::from_fn(m, |ys| {
+            let ys: Vec<bool> = ys.iter().zip(&constants)
+                .map(|(&y, c)| c.unwrap_or(y)).collect();
+            table[row(&ys)].unwrap().0
+        }))
+    }
+
+    /// Returns the value if the function is constant.
+    pub fn constant(&self) -> Option<bool> {
+        match *self {
+            Expr::False => Some(false),
+            Expr::True => Some(true),
+            Expr::If(ref a, ref b) => {
+                let val = a.constant()?;
+                if b.constant()? == val {Some(val)} else {None}
+            }
+        }
+    }
+
+    /// Builds a table of `f[p0 -> ... -> pn]` for all unary paths
+    /// and all functions up to `max_arity` arguments.
+    pub fn asympath_table(max_arity: usize) -> Vec<AsymPathEntry> {
+        let unary = Expr::unary();
+        let mut res = vec![];
+        for n in 0..max_arity + 1 {
+            for f in Expr::all(n) {
+                // Go through all combinations of paths.
+                let mut ind = vec![0; n + 1];
+                'ind: loop {
+                    let ps: Vec<Expr> = ind.iter().map(|&i| unary[i].clone()).collect();
+                    let g = f.find_asympath(&ps);
+                    res.push(AsymPathEntry {f: f.clone(), ps, g});
+                    for k in (0..ind.len()).rev() {
+                        ind[k] += 1;
+                        if ind[k] < unary.len() {continue 'ind};
+                        ind[k] = 0;
+                    }
+                    break;
+                }
+            }
+        }
+        res
+    }
+
     pub fn has_asympath(&self, ps: &[Expr], tension: bool) -> bool {
+        // Paths with more than one argument are decided by the truth table.
+        if ps.iter().any(|p| p.arity() != Some(1)) {
+            return self.find_asympath(ps).is_ok();
+        }
+
         // When both sub functions are the same,
         // the argument is irrelevant, so it does not matter
         // what the path does.
@@ -137,6 +381,15 @@ impl Expr {
     }
 }
 
+/// Returns the row in a truth table of argument values.
+fn row(args: &[bool]) -> usize {
+    args.iter().fold(0, |acc, &arg| (acc << 1) | arg as usize)
+}
+
+/// Returns the argument values of a row in a truth table.
+fn row_args(i: usize, n: usize) -> Vec<bool> {
+    (0..n).map(|k| (i >> (n - 1 - k)) & 1 == 1).collect()
+}
 
 #[cfg(test)]
 mod tests {
@@ -150,12 +403,193 @@ mod tests {
         assert!(Expr::true_1().is_irrelevant());
     }
 
+    #[test]
+    fn test_truth_table() {
+        assert_eq!(Expr::from_truth_table(&[false, false, false, true]), Some(Expr::and()));
+        assert_eq!(Expr::from_truth_table(&[false, true, true, true]), Some(Expr::or()));
+        assert_eq!(Expr::from_truth_table(&[true, false]), Some(Expr::not()));
+        assert_eq!(Expr::from_truth_table(&[true]), Some(Expr::True));
+        assert_eq!(Expr::from_truth_table(&[true, false, true]), None);
+        assert_eq!(Expr::and().truth_table(), Some(vec![false, false, false, true]));
+        assert_eq!(Expr::false_n(3).truth_table(), Some(vec![false; 8]));
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Expr::parse("a & b"), Ok(Expr::and()));
+        assert_eq!(Expr::parse("a | b"), Ok(Expr::or()));
+        assert_eq!(Expr::parse("!a"), Ok(Expr::not()));
+        assert_eq!(Expr::parse("a & !a"), Ok(Expr::false_1()));
+        assert_eq!(Expr::parse("0"), Ok(Expr::False));
+        assert_eq!(Expr::parse("a ^ b").unwrap().truth_table(),
+                   Some(vec![false, true, true, false]));
+        assert_eq!(Expr::parse("!(a | b) & c").unwrap().truth_table(),
+                   Some(vec![false, true, false, false, false, false, false, false]));
+        assert_eq!(Expr::parse_with(&["b", "a"], "a & !b").unwrap().truth_table(),
+                   Some(vec![false, true, false, false]));
+        assert!(Expr::parse_with(&["a"], "a & b").is_err());
+        assert!(Expr::parse("a &").is_err());
+        assert!(Expr::parse("(a").is_err());
+    }
+
+    #[test]
+    fn test_arity_eval() {
+        assert_eq!(Expr::True.arity(), Some(0));
+        assert_eq!(Expr::and().arity(), Some(2));
+        assert_eq!(Expr::true_n(4).arity(), Some(4));
+        assert_eq!(Expr::If(Arc::new(Expr::True), Arc::new(Expr::not())).arity(), None);
+
+        let f = Expr::parse("a & !b | c").unwrap();
+        assert_eq!(f.eval(&[true, false, false]), Some(true));
+        assert_eq!(f.eval(&[true, true, false]), Some(false));
+        assert_eq!(f.eval(&[false, true, true]), Some(true));
+        assert_eq!(f.eval(&[true, false]), None);
+    }
+
+    #[test]
+    fn test_reduce() {
+        let f = Expr::If(Arc::new(Expr::false_1()), Arc::new(Expr::false_1()));
+        let g = f.reduce();
+        assert_eq!(f, g);
+        assert!(f.is_irrelevant());
+        if let Expr::If(ref a, ref b) = g {
+            assert!(Arc::ptr_eq(a, b));
+        }
+    }
+
     #[test]
     fn test_sympath_not() {
         assert_eq!(Expr::and().sympath_not(), Expr::or());
         assert_eq!(Expr::or().sympath_not(), Expr::and());
     }
 
+    #[test]
+    fn test_find_asympath() {
+        use Expr as E;
+
+        // false_1[not -> id] <=> false_1
+        assert_eq!(E::false_1().find_asympath(&[E::not(), E::id()]), Ok(E::false_1()));
+        // false_1[false_1 -> not] <=> true_1
+        assert_eq!(E::false_1().find_asympath(&[E::false_1(), E::not()]), Ok(E::true_1()));
+        // not[not -> not] <=> not
+        assert_eq!(E::not().find_asympath(&[E::not(), E::not()]), Ok(E::not()));
+        // not[id -> not] <=> id
+        assert_eq!(E::not().find_asympath(&[E::id(), E::not()]), Ok(E::id()));
+        // not[false_1 -> not] <=> {}
+        assert_eq!(E::not().find_asympath(&[E::false_1(), E::not()]),
+                   Err(NoPath::Conflict(vec![false], vec![true])));
+        // and[not -> not -> not] <=> or
+        assert_eq!(E::and().find_asympath(&[E::not(), E::not(), E::not()]), Ok(E::or()));
+        // and[id -> true_1 -> id] <=> {}
+        assert!(E::and().find_asympath(&[E::id(), E::true_1(), E::id()]).is_err());
+        // and[id -> true_1 -> true_1] <=> true_2
+        assert_eq!(E::and().find_asympath(&[E::id(), E::true_1(), E::true_1()]),
+                   Ok(E::true_n(2)));
+        // The second argument is irrelevant, so it can be collapsed.
+        let fst = E::parse_with(&["a", "b"], "a").unwrap();
+        assert_eq!(fst.find_asympath(&[E::not(), E::false_1(), E::not()]), Ok(fst));
+
+        assert_eq!(E::not().find_asympath(&[E::not()]), Err(NoPath::Arity));
+        assert_eq!(E::not().find_asympath(&[E::and(), E::not()]), Err(NoPath::Arity));
+        assert_eq!(E::not().find_asympath(&[E::not(), E::and()]), Err(NoPath::NotUnary));
+    }
+
+    #[test]
+    fn test_asympath_table() {
+        let unary = Expr::all(1);
+        assert!(Expr::unary().iter().all(|p| unary.contains(p)));
+        assert_eq!(Expr::all(2).len(), 16);
+
+        let table = Expr::asympath_table(3);
+        // 2 * 4 + 4 * 4^2 + 16 * 4^3 + 256 * 4^4
+        assert_eq!(table.len(), 8 + 64 + 1024 + 65536);
+        for entry in &table {
+            if let Ok(ref g) = entry.g {
+                // Check `pn(f(x0, ...)) == g(p0(x0), ...)`.
+                let n = entry.ps.len() - 1;
+                for i in 0..1 << n {
+                    let args = row_args(i, n);
+                    let ys: Vec<bool> = args.iter().zip(&entry.ps)
+                        .map(|(&x, p)| p.eval(&[x]).unwrap()).collect();
+                    let ret = entry.f.eval(&args).unwrap();
+                    assert_eq!(entry.ps[n].eval(&[ret]), g.eval(&ys));
+                }
+            }
+            if entry.ps.len() <= 3 {
+                assert_eq!(entry.g.is_ok(), entry.f.has_asympath(&entry.ps, false),
+                           "{:?}", entry);
+            }
+        }
+    }
+
+    #[test]
+    fn test_binary_paths() {
+        use Expr as E;
+
+        let and_or_and = E::parse("a & b | c & d").unwrap();
+        // (a & b | c & d)[and x and -> id] <=> or
+        assert_eq!(and_or_and.find_asympath(&[E::and(), E::and(), E::id()]), Ok(E::or()));
+        assert!(and_or_and.has_asympath(&[E::and(), E::and(), E::id()], false));
+        // (a & b | c & d)[and x and -> not] <=> nor
+        assert_eq!(and_or_and.find_asympath(&[E::and(), E::and(), E::not()]),
+                   Ok(E::parse("!(a | b)").unwrap()));
+        // (a & b | c & d)[or x or -> id] <=> {}
+        assert_eq!(and_or_and.find_asympath(&[E::or(), E::or(), E::id()]),
+                   Err(NoPath::Conflict(vec![false, false, false, true],
+                                        vec![false, false, true, true])));
+        assert!(!and_or_and.has_asympath(&[E::or(), E::or(), E::id()], false));
+
+        // and[and -> id] <=> id
+        assert_eq!(E::and().find_asympath(&[E::and(), E::id()]), Ok(E::id()));
+        // ((a | b) & !c)[or x not -> id] <=> and
+        let f = E::parse("(a | b) & !c").unwrap();
+        assert_eq!(f.find_asympath(&[E::or(), E::not(), E::id()]), Ok(E::and()));
+        // ((a | b) & !c)[or x id -> id] <=> and_not
+        assert_eq!(f.find_asympath(&[E::or(), E::id(), E::id()]),
+                   Ok(E::parse("a & !b").unwrap()));
+        // ((a | b) & !c)[false_2 x id -> false_1] <=> false_1 x id
+        assert_eq!(f.find_asympath(&[E::false_n(2), E::id(), E::false_1()]),
+                   Ok(E::false_n(2)));
+
+        assert_eq!(E::and().find_asympath(&[E::and(), E::not(), E::id()]), Err(NoPath::Arity));
+        assert_eq!(E::and().find_asympath(&[E::and(), E::and()]), Err(NoPath::NotUnary));
+    }
+
+    #[test]
+    fn test_sympath() {
+        let xor = Expr::parse("a ^ b").unwrap();
+        let eq = Expr::parse("!(a ^ b)").unwrap();
+
+        // and[not] <=> or
+        assert_eq!(Expr::and().sympath(&Expr::not()), Some(Expr::or()));
+        // or[not] <=> and
+        assert_eq!(Expr::or().sympath(&Expr::not()), Some(Expr::and()));
+        // xor[not] <=> eq
+        assert_eq!(xor.sympath(&Expr::not()), Some(eq.clone()));
+        // eq[not] <=> xor
+        assert_eq!(eq.sympath(&Expr::not()), Some(xor.clone()));
+        // not[not] <=> not
+        assert_eq!(Expr::not().sympath(&Expr::not()), Some(Expr::not()));
+        // f[id] <=> f
+        assert_eq!(xor.sympath(&Expr::id()), Some(xor.clone()));
+        // and[false_1] <=> false_2
+        assert_eq!(Expr::and().sympath(&Expr::false_1()), Some(Expr::false_n(2)));
+        // xor[true_1] <=> true_2
+        assert_eq!(xor.sympath(&Expr::true_1()), Some(Expr::true_n(2)));
+        // false[not] <=> true
+        assert_eq!(Expr::False.sympath(&Expr::not()), Some(Expr::True));
+        // id[false_1] <=> false_1
+        assert_eq!(Expr::id().sympath(&Expr::false_1()), Some(Expr::false_1()));
+        // The path must be a unary function.
+        assert_eq!(Expr::not().sympath(&Expr::and()), None);
+
+        for n in 0..4 {
+            for f in Expr::all(n) {
+                assert_eq!(f.sympath(&Expr::not()), Some(f.sympath_not()));
+            }
+        }
+    }
+
     #[test]
     fn test_has_asympath() {
         // false[false_1] <=> false
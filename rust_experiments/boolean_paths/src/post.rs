@@ -0,0 +1,226 @@
+//! Classification of boolean functions by the maximal classes of Post's lattice.
+
+use std::fmt;
+
+use {row, row_args, Expr};
+
+/// Stores the membership in the maximal classes of Post's lattice.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct PostClass {
+    /// `f(false, ...) == false` (T0).
+    pub preserves_false: bool,
+    /// `f(true, ...) == true` (T1).
+    pub preserves_true: bool,
+    /// Monotone (M).
+    pub monotone: bool,
+    /// Linear, `c0 ^ (c1 & x1) ^ ...` (L).
+    pub linear: bool,
+    /// Self-dual, `!f(!x0, ...) == f(x0, ...)` (S).
+    pub self_dual: bool,
+}
+
+impl fmt::Display for PostClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let classes = [
+            (self.preserves_false, "T0"),
+            (self.preserves_true, "T1"),
+            (self.monotone, "M"),
+            (self.linear, "L"),
+            (self.self_dual, "S"),
+        ];
+        let names: Vec<&str> = classes.iter().filter(|c| c.0).map(|c| c.1).collect();
+        if names.is_empty() {
+            write!(f, "-")
+        } else {
+            write!(f, "{}", names.join(" "))
+        }
+    }
+}
+
+impl Expr {
+    /// Returns `true` if `f(false, ...) == false`.
+    pub fn preserves_false(&self) -> Option<bool> {
+        let n = self.arity()?;
+        self.eval(&vec![false; n]).map(|val| !val)
+    }
+
+    /// Returns `true` if `f(true, ...) == true`.
+    pub fn preserves_true(&self) -> Option<bool> {
+        let n = self.arity()?;
+        self.eval(&vec![true; n])
+    }
+
+    /// Returns `true` if changing an argument from `false` to `true`
+    /// never changes the result from `true` to `false`.
+    pub fn is_monotone(&self) -> Option<bool> {
+        let table = self.truth_table()?;
+        for (i, &a) in table.iter().enumerate() {
+            for (j, &b) in table.iter().enumerate() {
+                // Row `i` is below row `j`.
+                if i & j == i && a && !b {return Some(false)};
+            }
+        }
+        Some(true)
+    }
+
+    /// Returns `true` if the function is an exclusive or of arguments and a constant.
+    ///
+    /// Uses the algebraic normal form, which has no terms with more than one argument.
+    pub fn is_linear(&self) -> Option<bool> {
+        let mut anf = self.truth_table()?;
+        // Transform truth table to coefficients of algebraic normal form.
+        let mut step = 1;
+        while step < anf.len() {
+            for i in 0..anf.len() {
+                if i & step != 0 {
+                    anf[i] ^= anf[i ^ step];
+                }
+            }
+            step <<= 1;
+        }
+        Some(anf.iter().enumerate().all(|(i, &c)| !c || i.count_ones() <= 1))
+    }
+
+    /// Returns `true` if `!f(!x0, ...) == f(x0, ...)`.
+    pub fn is_self_dual(&self) -> Option<bool> {
+        let n = self.arity()?;
+        let table = self.truth_table()?;
+        Some((0..table.len()).all(|i| {
+            let not_args: Vec<bool> = row_args(i, n).into_iter().map(|x| !x).collect();
+            table[row(&not_args)] != table[i]
+        }))
+    }
+
+    /// Classifies the function by the maximal classes of Post's lattice.
+    pub fn post_class(&self) -> Option<PostClass> {
+        Some(PostClass {
+            preserves_false: self.preserves_false()?,
+            preserves_true: self.preserves_true()?,
+            monotone: self.is_monotone()?,
+            linear: self.is_linear()?,
+            self_dual: self.is_self_dual()?,
+        })
+    }
+}
+
+/// Stores a function in a Post class report.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PostEntry {
+    /// The function.
+    pub f: Expr,
+    /// The symmetric paths `f[p]` by unary functions.
+    pub sympaths: Vec<(Expr, Expr)>,
+    /// The asymmetric paths `f[p0 x ... -> pn]` by unary functions that exist,
+    /// stored as the paths of arguments followed by the path of the result.
+    pub asympaths: Vec<(Vec<Expr>, Expr)>,
+}
+
+/// Stores the functions of a Post class.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PostGroup {
+    /// The class.
+    pub class: PostClass,
+    /// The functions in the class.
+    pub entries: Vec<PostEntry>,
+}
+
+/// Groups all functions up to `max_arity` arguments by Post class.
+pub fn post_groups(max_arity: usize) -> Vec<PostGroup> {
+    let mut groups: Vec<PostGroup> = vec![];
+    for entry in Expr::asympath_table(max_arity) {
+        let class = entry.f.post_class().unwrap();
+        let i = match groups.iter().position(|g| g.class == class) {
+            Some(i) => i,
+            None => {
+                groups.push(PostGroup {class, entries: vec![]});
+                groups.len() - 1
+            }
+        };
+        let group = &mut groups[i];
+        let j = match group.entries.iter().position(|e| e.f == entry.f) {
+            Some(j) => j,
+            None => {
+                let sympaths = Expr::unary().into_iter()
+                    .filter_map(|p| entry.f.sympath(&p).map(|g| (p, g)))
+                    .collect();
+                group.entries.push(PostEntry {f: entry.f.clone(), sympaths, asympaths: vec![]});
+                group.entries.len() - 1
+            }
+        };
+        if let Ok(g) = entry.g {
+            group.entries[j].asympaths.push((entry.ps, g));
+        }
+    }
+    groups
+}
+
+/// Generates a report of all functions up to `max_arity` arguments grouped by Post class.
+///
+/// Functions are written as truth tables, e.g. `0001` for `and`.
+/// Symmetric paths are listed after the function,
+/// followed by a line with the asymmetric paths, e.g. `[01 x 01 -> 01] = 0001`.
+pub fn post_report(max_arity: usize) -> String {
+    let bits = |f: &Expr| -> String {
+        f.truth_table().unwrap().iter().map(|&b| if b {'1'} else {'0'}).collect()
+    };
+    let mut res = String::new();
+    for group in post_groups(max_arity) {
+        res.push_str(&format!("{} ({} functions)\n", group.class, group.entries.len()));
+        for entry in &group.entries {
+            let sympaths: Vec<String> = entry.sympaths.iter()
+                .map(|(p, g)| format!("[{}] = {}", bits(p), bits(g))).collect();
+            let asympaths: Vec<String> = entry.asympaths.iter()
+                .map(|(ps, g)| {
+                    let (pn, ps) = ps.split_last().unwrap();
+                    let ps: Vec<String> = ps.iter().map(&bits).collect();
+                    format!("[{} -> {}] = {}", ps.join(" x "), bits(pn), bits(g))
+                }).collect();
+            res.push_str(&format!("  {}: {}\n", bits(&entry.f), sympaths.join(", ")));
+            res.push_str(&format!("    {}\n", asympaths.join(", ")));
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classes() {
+        let class = |s: &str| Expr::parse(s).unwrap().post_class().unwrap().to_string();
+        assert_eq!(class("a & b"), "T0 T1 M");
+        assert_eq!(class("a | b"), "T0 T1 M");
+        assert_eq!(class("a ^ b"), "T0 L");
+        assert_eq!(class("!(a ^ b)"), "T1 L");
+        assert_eq!(class("!a"), "L S");
+        assert_eq!(class("a"), "T0 T1 M L S");
+        assert_eq!(class("!(a & b)"), "-");
+        assert_eq!(class("a & b | b & c | a & c"), "T0 T1 M S");
+        assert_eq!(class("a ^ b ^ c"), "T0 T1 L S");
+        assert_eq!(Expr::True.post_class().unwrap().to_string(), "T1 M L");
+    }
+
+    #[test]
+    fn self_dual_is_sympath_not() {
+        for n in 0..4 {
+            for f in Expr::all(n) {
+                let self_dual = f.is_self_dual().unwrap();
+                assert_eq!(self_dual, f.sympath(&Expr::not()) == Some(f.clone()));
+            }
+        }
+    }
+
+    #[test]
+    fn report() {
+        let groups = post_groups(2);
+        assert_eq!(groups.iter().map(|g| g.entries.len()).sum::<usize>(), 2 + 4 + 16);
+        let report = post_report(2);
+        assert!(report.contains("  0001: [00] = 0000, [10] = 0111, [01] = 0001, [11] = 1111\n"));
+        // `and` has the asymmetric path `[not x not -> not] = or`.
+        let and = &groups.iter().flat_map(|g| &g.entries)
+            .find(|e| e.f == Expr::parse("a & b").unwrap()).unwrap();
+        assert!(and.asympaths.contains(&(vec![Expr::not(); 3], Expr::parse("a | b").unwrap())));
+        assert!(report.contains("[10 x 10 -> 10] = 0111"));
+    }
+}
@@ -0,0 +1,575 @@
+//! A CDCL SAT solver, and path equations decided by the solver.
+//!
+//! An alternative to `Expr` and `bdd` for checking claims about functions
+//! with many arguments, where truth tables or diagrams get too large.
+//! Functions are encoded as clauses with one variable per `If` node.
+//! Shared sub functions are encoded once, and gates are normalized and shared,
+//! so structurally equal parts of a claim get the same variable.
+//!
+//! The solver uses two watched literals per clause, learns clauses by
+//! the first unique implication point, and picks variables by activity.
+
+use std::collections::HashMap;
+use std::ops::Not;
+
+use Expr;
+
+/// A variable of the solver.
+pub type Var = usize;
+
+/// A variable or its negation.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Lit(usize);
+
+impl Lit {
+    /// Creates a positive literal.
+    pub fn pos(var: Var) -> Lit {Lit(var << 1)}
+
+    /// Creates a negative literal.
+    pub fn neg(var: Var) -> Lit {Lit(var << 1 | 1)}
+
+    /// Returns the variable.
+    pub fn var(self) -> Var {self.0 >> 1}
+
+    /// Returns `true` if the literal is negated.
+    pub fn is_neg(self) -> bool {self.0 & 1 == 1}
+}
+
+impl Not for Lit {
+    type Output = Lit;
+    fn not(self) -> Lit {Lit(self.0 ^ 1)}
+}
+
+/// Stores clauses and searches for an assignment satisfying all of them.
+pub struct Solver {
+    clauses: Vec<Vec<Lit>>,
+    // Clauses watching each literal, by literal index.
+    watches: Vec<Vec<usize>>,
+    assigns: Vec<Option<bool>>,
+    levels: Vec<usize>,
+    reasons: Vec<Option<usize>>,
+    trail: Vec<Lit>,
+    trail_lim: Vec<usize>,
+    queue_head: usize,
+    activity: Vec<f64>,
+    var_inc: f64,
+    phases: Vec<bool>,
+    seen: Vec<bool>,
+    unsat: bool,
+    true_lit: Option<Lit>,
+    gates: HashMap<Gate, Lit>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Gate {
+    Ite(Lit, Lit, Lit),
+    Xnor(Lit, Lit),
+}
+
+impl Default for Solver {
+    fn default() -> Solver {Solver::new()}
+}
+
+impl Solver {
+    /// Creates a solver without variables.
+    pub fn new() -> Solver {
+        Solver {
+            clauses: vec![],
+            watches: vec![],
+            assigns: vec![],
+            levels: vec![],
+            reasons: vec![],
+            trail: vec![],
+            trail_lim: vec![],
+            queue_head: 0,
+            activity: vec![],
+            var_inc: 1.0,
+            phases: vec![],
+            seen: vec![],
+            unsat: false,
+            true_lit: None,
+            gates: HashMap::new(),
+        }
+    }
+
+    /// Returns the number of variables.
+    pub fn vars(&self) -> usize {self.assigns.len()}
+
+    /// Creates a new variable.
+    pub fn new_var(&mut self) -> Var {
+        let var = self.assigns.len();
+        self.assigns.push(None);
+        self.levels.push(0);
+        self.reasons.push(None);
+        self.activity.push(0.0);
+        self.phases.push(false);
+        self.seen.push(false);
+        self.watches.push(vec![]);
+        self.watches.push(vec![]);
+        var
+    }
+
+    /// Adds a clause, the disjunction of the literals.
+    ///
+    /// Returns `false` if the clauses are known to be unsatisfiable.
+    pub fn add_clause(&mut self, lits: &[Lit]) -> bool {
+        if self.unsat {return false};
+        self.backtrack(0);
+        let mut clause: Vec<Lit> = vec![];
+        for &lit in lits {
+            match self.value(lit) {
+                Some(true) => return true,
+                Some(false) => {}
+                None => {
+                    if clause.contains(&!lit) {return true};
+                    if !clause.contains(&lit) {clause.push(lit)};
+                }
+            }
+        }
+        match clause.len() {
+            0 => self.unsat = true,
+            1 => {
+                self.enqueue(clause[0], None);
+                if self.propagate().is_some() {self.unsat = true};
+            }
+            _ => {self.attach(clause);}
+        }
+        !self.unsat
+    }
+
+    /// Searches for an assignment satisfying all clauses.
+    ///
+    /// Returns the value of every variable, or `None` if the clauses are unsatisfiable.
+    pub fn solve(&mut self) -> Option<Vec<bool>> {
+        if self.unsat {return None};
+        let mut conflicts = 0;
+        let mut restart_limit = 100;
+        loop {
+            if let Some(confl) = self.propagate() {
+                if self.trail_lim.is_empty() {
+                    self.unsat = true;
+                    return None;
+                }
+                conflicts += 1;
+                let (learnt, level) = self.analyze(confl);
+                self.backtrack(level);
+                if learnt.len() == 1 {
+                    self.enqueue(learnt[0], None);
+                } else {
+                    let lit = learnt[0];
+                    let ci = self.attach(learnt);
+                    self.enqueue(lit, Some(ci));
+                }
+                self.var_inc /= 0.95;
+            } else if conflicts >= restart_limit {
+                conflicts = 0;
+                restart_limit += restart_limit / 2;
+                self.backtrack(0);
+            } else {
+                match self.pick() {
+                    Some(var) => {
+                        self.trail_lim.push(self.trail.len());
+                        let lit = if self.phases[var] {Lit::pos(var)} else {Lit::neg(var)};
+                        self.enqueue(lit, None);
+                    }
+                    None => {
+                        let model = self.assigns.iter().map(|v| v.unwrap()).collect();
+                        self.backtrack(0);
+                        return Some(model);
+                    }
+                }
+            }
+        }
+    }
+
+    fn value(&self, lit: Lit) -> Option<bool> {
+        self.assigns[lit.var()].map(|v| v != lit.is_neg())
+    }
+
+    fn attach(&mut self, clause: Vec<Lit>) -> usize {
+        let ci = self.clauses.len();
+        self.watches[clause[0].0].push(ci);
+        self.watches[clause[1].0].push(ci);
+        self.clauses.push(clause);
+        ci
+    }
+
+    fn enqueue(&mut self, lit: Lit, reason: Option<usize>) {
+        let var = lit.var();
+        self.assigns[var] = Some(!lit.is_neg());
+        self.levels[var] = self.trail_lim.len();
+        self.reasons[var] = reason;
+        self.trail.push(lit);
+    }
+
+    fn backtrack(&mut self, level: usize) {
+        if self.trail_lim.len() <= level {return};
+        let start = self.trail_lim[level];
+        for &lit in &self.trail[start..] {
+            let var = lit.var();
+            self.phases[var] = !lit.is_neg();
+            self.assigns[var] = None;
+            self.reasons[var] = None;
+        }
+        self.trail.truncate(start);
+        self.trail_lim.truncate(level);
+        self.queue_head = self.queue_head.min(start);
+    }
+
+    // Returns a conflicting clause, if any.
+    fn propagate(&mut self) -> Option<usize> {
+        while self.queue_head < self.trail.len() {
+            let false_lit = !self.trail[self.queue_head];
+            self.queue_head += 1;
+            let watching = ::std::mem::take(&mut self.watches[false_lit.0]);
+            let mut keep = Vec::with_capacity(watching.len());
+            let mut conflict = None;
+            for (i, &ci) in watching.iter().enumerate() {
+                if conflict.is_some() {
+                    keep.extend_from_slice(&watching[i..]);
+                    break;
+                }
+                // Make sure the false literal is at position 1.
+                if self.clauses[ci][0] == false_lit {self.clauses[ci].swap(0, 1)};
+                let first = self.clauses[ci][0];
+                if self.value(first) == Some(true) {
+                    keep.push(ci);
+                    continue;
+                }
+                let n = self.clauses[ci].len();
+                if let Some(k) = (2..n).find(|&k| self.value(self.clauses[ci][k]) != Some(false)) {
+                    self.clauses[ci].swap(1, k);
+                    let lit = self.clauses[ci][1];
+                    self.watches[lit.0].push(ci);
+                    continue;
+                }
+                keep.push(ci);
+                if self.value(first) == Some(false) {
+                    conflict = Some(ci);
+                } else {
+                    self.enqueue(first, Some(ci));
+                }
+            }
+            self.watches[false_lit.0] = keep;
+            if conflict.is_some() {return conflict};
+        }
+        None
+    }
+
+    // Learns a clause from a conflict, where the first literal is asserted
+    // after backtracking to the returned level.
+    fn analyze(&mut self, confl: usize) -> (Vec<Lit>, usize) {
+        let level = self.trail_lim.len();
+        let mut learnt = vec![Lit(0)];
+        let mut counter = 0;
+        let mut p: Option<Lit> = None;
+        let mut ci = confl;
+        let mut index = self.trail.len();
+        loop {
+            let start = if p.is_some() {1} else {0};
+            for k in start..self.clauses[ci].len() {
+                let q = self.clauses[ci][k];
+                let var = q.var();
+                if self.seen[var] || self.levels[var] == 0 {continue};
+                self.seen[var] = true;
+                self.bump(var);
+                if self.levels[var] == level {counter += 1} else {learnt.push(q)};
+            }
+            loop {
+                index -= 1;
+                if self.seen[self.trail[index].var()] {break};
+            }
+            let lit = self.trail[index];
+            self.seen[lit.var()] = false;
+            p = Some(lit);
+            counter -= 1;
+            if counter == 0 {break};
+            ci = self.reasons[lit.var()].unwrap();
+        }
+        learnt[0] = !p.unwrap();
+        for lit in &learnt[1..] {self.seen[lit.var()] = false};
+        // Watch the literal from the highest level below the conflict.
+        let mut back = 0;
+        for k in 1..learnt.len() {
+            let l = self.levels[learnt[k].var()];
+            if l > back {
+                back = l;
+                learnt.swap(1, k);
+            }
+        }
+        (learnt, back)
+    }
+
+    fn bump(&mut self, var: Var) {
+        self.activity[var] += self.var_inc;
+        if self.activity[var] > 1e100 {
+            for a in &mut self.activity {*a *= 1e-100};
+            self.var_inc *= 1e-100;
+        }
+    }
+
+    fn pick(&self) -> Option<Var> {
+        let mut res: Option<Var> = None;
+        for var in 0..self.assigns.len() {
+            if self.assigns[var].is_some() {continue};
+            if res.map(|r| self.activity[var] > self.activity[r]).unwrap_or(true) {
+                res = Some(var);
+            }
+        }
+        res
+    }
+
+    /// Returns a literal with a constant value.
+    pub fn constant(&mut self, val: bool) -> Lit {
+        let t = match self.true_lit {
+            Some(t) => t,
+            None => {
+                let t = Lit::pos(self.new_var());
+                self.add_clause(&[t]);
+                self.true_lit = Some(t);
+                t
+            }
+        };
+        if val {t} else {!t}
+    }
+
+    /// Returns a literal equal to `if c {a} else {b}`.
+    ///
+    /// Gates are normalized and shared, so equal gates get the same literal.
+    pub fn ite(&mut self, c: Lit, a: Lit, b: Lit) -> Lit {
+        if c.is_neg() {return self.ite(!c, b, a)};
+        let (t, f) = (self.constant(true), self.constant(false));
+        if c == t {return a};
+        if c == f {return b};
+        if a == c {return self.ite(c, t, b)};
+        if a == !c {return self.ite(c, f, b)};
+        if b == c {return self.ite(c, a, f)};
+        if b == !c {return self.ite(c, a, t)};
+        if a == b {return a};
+        if a == t && b == f {return c};
+        if a == f && b == t {return !c};
+        if a == !b {return self.xnor(c, a)};
+        if a.is_neg() {return !self.ite(c, !a, !b)};
+        if let Some(&out) = self.gates.get(&Gate::Ite(c, a, b)) {return out};
+        let out = Lit::pos(self.new_var());
+        self.add_clause(&[!c, !a, out]);
+        self.add_clause(&[!c, a, !out]);
+        self.add_clause(&[c, !b, out]);
+        self.add_clause(&[c, b, !out]);
+        self.add_clause(&[!a, !b, out]);
+        self.add_clause(&[a, b, !out]);
+        self.gates.insert(Gate::Ite(c, a, b), out);
+        out
+    }
+
+    /// Returns a literal equal to `a == b`.
+    pub fn xnor(&mut self, a: Lit, b: Lit) -> Lit {
+        if a.is_neg() {return !self.xnor(!a, b)};
+        if b.is_neg() {return !self.xnor(a, !b)};
+        if a == b {return self.constant(true)};
+        let (a, b) = if a.0 < b.0 {(a, b)} else {(b, a)};
+        let t = self.constant(true);
+        if a == t {return b};
+        if let Some(&out) = self.gates.get(&Gate::Xnor(a, b)) {return out};
+        let out = Lit::pos(self.new_var());
+        self.add_clause(&[!a, !b, out]);
+        self.add_clause(&[a, b, out]);
+        self.add_clause(&[!a, b, !out]);
+        self.add_clause(&[a, !b, !out]);
+        self.gates.insert(Gate::Xnor(a, b), out);
+        out
+    }
+
+    /// Encodes a function applied to the arguments, returning a literal equal to the result.
+    ///
+    /// Returns `None` if the number of arguments does not match.
+    pub fn encode(&mut self, expr: &Expr, args: &[Lit]) -> Option<Lit> {
+        fn enc(
+            solver: &mut Solver,
+            expr: &Expr,
+            args: &[Lit],
+            depth: usize,
+            cache: &mut HashMap<(*const Expr, usize), Lit>,
+        ) -> Option<Lit> {
+            let key = (expr as *const Expr, depth);
+            if let Some(&lit) = cache.get(&key) {return Some(lit)};
+            let res = match *expr {
+                Expr::False if depth == args.len() => solver.constant(false),
+                Expr::True if depth == args.len() => solver.constant(true),
+                Expr::If(ref a, ref b) if depth < args.len() => {
+                    let a = enc(solver, a, args, depth + 1, cache)?;
+                    let b = enc(solver, b, args, depth + 1, cache)?;
+                    solver.ite(args[depth], a, b)
+                }
+                _ => return None,
+            };
+            cache.insert(key, res);
+            Some(res)
+        }
+        enc(self, expr, args, 0, &mut HashMap::new())
+    }
+}
+
+// Returns the number of arguments along the first sub functions.
+//
+// This does not visit shared sub functions more than once, unlike `Expr::arity`.
+// Other sub functions are checked when encoded.
+fn depth(expr: &Expr) -> usize {
+    let mut n = 0;
+    let mut expr = expr;
+    while let Expr::If(ref a, _) = *expr {
+        n += 1;
+        expr = a;
+    }
+    n
+}
+
+/// Returns arguments where `p(f(x)) != g(p(x0), ..., p(xn))`.
+///
+/// Returns `None` if the symmetric path `f[p] <=> g` holds for all arguments.
+/// Returns an error if the arities of `f`, `p` and `g` do not match.
+pub fn sympath_counterexample(f: &Expr, p: &Expr, g: &Expr) -> Result<Option<Vec<bool>>, String> {
+    let n = depth(f);
+    let mut solver = Solver::new();
+    let xs: Vec<Lit> = (0..n).map(|_| Lit::pos(solver.new_var())).collect();
+    let fx = solver.encode(f, &xs).ok_or("Expected function with well defined arity")?;
+    let lhs = solver.encode(p, &[fx]).ok_or("Expected unary path")?;
+    let pxs: Vec<Lit> = xs.iter().map(|&x| solver.encode(p, &[x]).unwrap()).collect();
+    let rhs = solver.encode(g, &pxs)
+        .ok_or_else(|| format!("Expected path function with {} arguments", n))?;
+    solver.add_clause(&[lhs, rhs]);
+    solver.add_clause(&[!lhs, !rhs]);
+    Ok(solver.solve().map(|model| xs.iter().map(|x| model[x.var()]).collect()))
+}
+
+/// Returns arguments where `a` and `b` differ.
+///
+/// Returns `None` if the functions are equal.
+/// Returns an error if the arities do not match.
+pub fn difference(a: &Expr, b: &Expr) -> Result<Option<Vec<bool>>, String> {
+    let n = depth(a);
+    let mut solver = Solver::new();
+    let xs: Vec<Lit> = (0..n).map(|_| Lit::pos(solver.new_var())).collect();
+    let ax = solver.encode(a, &xs).ok_or("Expected function with well defined arity")?;
+    let bx = solver.encode(b, &xs).ok_or_else(|| format!("Expected function with {} arguments", n))?;
+    solver.add_clause(&[ax, bx]);
+    solver.add_clause(&[!ax, !bx]);
+    Ok(solver.solve().map(|model| xs.iter().map(|x| model[x.var()]).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    // Parity of `n` arguments and its negation, with sub functions shared.
+    fn parity(n: usize) -> (Expr, Expr) {
+        let (mut odd, mut even) = (Arc::new(Expr::False), Arc::new(Expr::True));
+        for _ in 0..n {
+            let next_odd = Arc::new(Expr::If(even.clone(), odd.clone()));
+            even = Arc::new(Expr::If(odd, even));
+            odd = next_odd;
+        }
+        ((*odd).clone(), (*even).clone())
+    }
+
+    // `true` when all `n` arguments are `true`, with sub functions shared.
+    fn and(n: usize) -> Expr {
+        let mut res = Arc::new(Expr::True);
+        let mut false_ = Arc::new(Expr::False);
+        for _ in 0..n {
+            res = Arc::new(Expr::If(res, false_.clone()));
+            false_ = Arc::new(Expr::If(false_.clone(), false_));
+        }
+        (*res).clone()
+    }
+
+    fn or(n: usize) -> Expr {
+        let mut res = Arc::new(Expr::False);
+        let mut true_ = Arc::new(Expr::True);
+        for _ in 0..n {
+            res = Arc::new(Expr::If(true_.clone(), res));
+            true_ = Arc::new(Expr::If(true_.clone(), true_));
+        }
+        (*res).clone()
+    }
+
+    #[test]
+    fn clauses() {
+        let mut solver = Solver::new();
+        let (a, b) = (solver.new_var(), solver.new_var());
+        assert!(solver.add_clause(&[Lit::pos(a), Lit::pos(b)]));
+        assert!(solver.add_clause(&[Lit::neg(a)]));
+        assert_eq!(solver.solve(), Some(vec![false, true]));
+        assert!(!solver.add_clause(&[Lit::neg(b)]));
+        assert_eq!(solver.solve(), None);
+    }
+
+    #[test]
+    fn pigeon_hole() {
+        // Four pigeons do not fit in three holes.
+        let mut solver = Solver::new();
+        let x: Vec<Vec<Var>> = (0..4).map(|_| (0..3).map(|_| solver.new_var()).collect()).collect();
+        for pigeon in &x {
+            let clause: Vec<Lit> = pigeon.iter().map(|&v| Lit::pos(v)).collect();
+            solver.add_clause(&clause);
+        }
+        for (i, a) in x.iter().enumerate() {
+            for b in &x[i + 1..] {
+                for hole in 0..3 {
+                    solver.add_clause(&[Lit::neg(a[hole]), Lit::neg(b[hole])]);
+                }
+            }
+        }
+        assert_eq!(solver.solve(), None);
+    }
+
+    #[test]
+    fn agrees_with_sympath() {
+        for n in 0..3 {
+            for f in Expr::all(n) {
+                for p in Expr::unary() {
+                    if let Some(g) = f.sympath(&p) {
+                        assert_eq!(sympath_counterexample(&f, &p, &g), Ok(None));
+                    }
+                    for g in Expr::all(n) {
+                        let holds = (0..1 << n).all(|i| {
+                            let x: Vec<bool> = (0..n).map(|k| (i >> k) & 1 == 1).collect();
+                            let px: Vec<bool> = x.iter().map(|&a| p.eval(&[a]).unwrap()).collect();
+                            p.eval(&[f.eval(&x).unwrap()]) == g.eval(&px)
+                        });
+                        match sympath_counterexample(&f, &p, &g).unwrap() {
+                            None => assert!(holds),
+                            Some(x) => {
+                                let px: Vec<bool> = x.iter().map(|&a| p.eval(&[a]).unwrap()).collect();
+                                assert_ne!(p.eval(&[f.eval(&x).unwrap()]), g.eval(&px));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        assert!(sympath_counterexample(&Expr::and(), &Expr::and(), &Expr::or()).is_err());
+        assert!(sympath_counterexample(&Expr::and(), &Expr::not(), &Expr::not()).is_err());
+    }
+
+    #[test]
+    fn many_arguments() {
+        let n = 64;
+        // `and[not] <=> or`.
+        assert_eq!(sympath_counterexample(&and(n), &Expr::not(), &or(n)), Ok(None));
+        assert!(sympath_counterexample(&and(n), &Expr::not(), &and(n)).unwrap().is_some());
+        // `odd[not] <=> even` for an even number of arguments, and `odd[not] <=> odd` otherwise.
+        let (odd, even) = parity(n);
+        assert_eq!(sympath_counterexample(&odd, &Expr::not(), &even), Ok(None));
+        let x = sympath_counterexample(&odd, &Expr::not(), &odd).unwrap().unwrap();
+        assert_eq!(x.len(), n);
+        let (odd, _) = parity(n + 1);
+        assert_eq!(sympath_counterexample(&odd, &Expr::not(), &odd), Ok(None));
+        let (odd, even) = parity(n);
+        assert_eq!(difference(&odd, &odd), Ok(None));
+        let x = difference(&and(n), &odd).unwrap().unwrap();
+        assert_ne!(and(n).eval(&x), odd.eval(&x));
+        assert_eq!(difference(&odd, &even).unwrap().map(|x| x.len()), Some(n));
+    }
+}
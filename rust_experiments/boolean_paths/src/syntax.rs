@@ -0,0 +1,130 @@
+//! A small expression syntax for boolean functions, e.g. `a & !b`.
+//!
+//! Operators from highest to lowest precedence:
+//! `!` (not), `&` (and), `^` (xor), `|` (or).
+//! The constants are `0` and `1`.
+
+/// Stores a parsed expression.
+pub enum Ast {
+    Const(bool),
+    Var(usize),
+    Not(Box<Ast>),
+    And(Box<Ast>, Box<Ast>),
+    Xor(Box<Ast>, Box<Ast>),
+    Or(Box<Ast>, Box<Ast>),
+}
+
+impl Ast {
+    /// Evaluates with values of variables.
+    pub fn eval(&self, args: &[bool]) -> bool {
+        match *self {
+            Ast::Const(val) => val,
+            Ast::Var(i) => args[i],
+            Ast::Not(ref a) => !a.eval(args),
+            Ast::And(ref a, ref b) => a.eval(args) && b.eval(args),
+            Ast::Xor(ref a, ref b) => a.eval(args) != b.eval(args),
+            Ast::Or(ref a, ref b) => a.eval(args) || b.eval(args),
+        }
+    }
+}
+
+/// Parses an expression.
+///
+/// New variables are added to `vars` in the order they appear.
+pub fn parse(source: &str, vars: &mut Vec<String>, fixed: bool) -> Result<Ast, String> {
+    let mut parser = Parser {chars: source.chars().collect(), pos: 0, vars, fixed};
+    let ast = parser.or()?;
+    parser.skip_whitespace();
+    if parser.pos < parser.chars.len() {
+        return Err(format!("Unexpected `{}` at {}", parser.chars[parser.pos], parser.pos));
+    }
+    Ok(ast)
+}
+
+struct Parser<'a> {
+    chars: Vec<char>,
+    pos: usize,
+    vars: &'a mut Vec<String>,
+    // Whether new variables are not allowed.
+    fixed: bool,
+}
+
+impl<'a> Parser<'a> {
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.chars.len() && self.chars[self.pos].is_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn try_char(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.chars.get(self.pos) == Some(&c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn or(&mut self) -> Result<Ast, String> {
+        let mut a = self.xor()?;
+        while self.try_char('|') {
+            a = Ast::Or(Box::new(a), Box::new(self.xor()?));
+        }
+        Ok(a)
+    }
+
+    fn xor(&mut self) -> Result<Ast, String> {
+        let mut a = self.and()?;
+        while self.try_char('^') {
+            a = Ast::Xor(Box::new(a), Box::new(self.and()?));
+        }
+        Ok(a)
+    }
+
+    fn and(&mut self) -> Result<Ast, String> {
+        let mut a = self.not()?;
+        while self.try_char('&') {
+            a = Ast::And(Box::new(a), Box::new(self.not()?));
+        }
+        Ok(a)
+    }
+
+    fn not(&mut self) -> Result<Ast, String> {
+        if self.try_char('!') {
+            Ok(Ast::Not(Box::new(self.not()?)))
+        } else {
+            self.atom()
+        }
+    }
+
+    fn atom(&mut self) -> Result<Ast, String> {
+        if self.try_char('(') {
+            let a = self.or()?;
+            if !self.try_char(')') {
+                return Err(format!("Expected `)` at {}", self.pos));
+            }
+            return Ok(a);
+        }
+        if self.try_char('0') {return Ok(Ast::Const(false))};
+        if self.try_char('1') {return Ok(Ast::Const(true))};
+        let start = self.pos;
+        while self.pos < self.chars.len() &&
+              (self.chars[self.pos].is_alphanumeric() || self.chars[self.pos] == '_')
+        {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return Err(format!("Expected variable at {}", self.pos));
+        }
+        let name: String = self.chars[start..self.pos].iter().collect();
+        match self.vars.iter().position(|v| v == &name) {
+            Some(i) => Ok(Ast::Var(i)),
+            None if self.fixed => Err(format!("Unknown variable `{}`", name)),
+            None => {
+                self.vars.push(name);
+                Ok(Ast::Var(self.vars.len() - 1))
+            }
+        }
+    }
+}
@@ -0,0 +1,198 @@
+//! Tables of paths, as in the alphabetic lists of paths.
+//!
+//! Functions are keyed by name, e.g. `and[not] <=> or`.
+//! Functions without a standard name are written as truth tables, e.g. `00010111`.
+
+use Expr;
+
+/// Stores a table with a header.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Table {
+    /// The column names.
+    pub header: Vec<String>,
+    /// The rows, sorted by name of function.
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    fn new(header: &[&str], mut rows: Vec<Vec<String>>) -> Table {
+        rows.sort();
+        rows.dedup();
+        Table {header: header.iter().map(|s| s.to_string()).collect(), rows}
+    }
+
+    /// Writes the table as comma separated values.
+    pub fn to_csv(&self) -> String {
+        fn field(s: &str) -> String {
+            if s.contains(',') || s.contains('"') || s.contains('\n') {
+                format!("\"{}\"", s.replace('"', "\"\""))
+            } else {
+                s.to_string()
+            }
+        }
+        let mut res = String::new();
+        for row in Some(&self.header).into_iter().chain(&self.rows) {
+            let fields: Vec<String> = row.iter().map(|s| field(s)).collect();
+            res.push_str(&fields.join(","));
+            res.push('\n');
+        }
+        res
+    }
+
+    /// Writes the table in Markdown.
+    pub fn to_markdown(&self) -> String {
+        let mut res = String::new();
+        let line = |row: &[String]| format!("| {} |\n", row.join(" | "));
+        res.push_str(&line(&self.header));
+        res.push_str(&format!("|{}\n", "---|".repeat(self.header.len())));
+        for row in &self.rows {
+            res.push_str(&line(row));
+        }
+        res
+    }
+}
+
+impl Expr {
+    /// Returns the standard name of the function,
+    /// or the truth table if it has no standard name.
+    pub fn name(&self) -> String {
+        let table = match self.truth_table() {
+            None => { return format!("{:?}", self); }
+            Some(table) => table,
+        };
+        let bits: String = table.iter().map(|&b| if b {'1'} else {'0'}).collect();
+        let n = self.arity().unwrap();
+        let name = match (n, &*bits) {
+            (0, "0") => "false",
+            (0, "1") => "true",
+            (1, "10") => "not",
+            (1, "01") => "id",
+            (2, "0001") => "and",
+            (2, "0010") => "exc",
+            (2, "0011") => "fst",
+            (2, "0100") => "rexc",
+            (2, "0101") => "snd",
+            (2, "0110") => "xor",
+            (2, "0111") => "or",
+            (2, "1000") => "nor",
+            (2, "1001") => "eq",
+            (2, "1010") => "not·snd",
+            (2, "1011") => "nrexc",
+            (2, "1100") => "not·fst",
+            (2, "1101") => "nexc",
+            (2, "1110") => "nand",
+            _ => match self.constant() {
+                Some(false) => { return format!("false_{}", n); }
+                Some(true) => { return format!("true_{}", n); }
+                None => { return bits; }
+            }
+        };
+        name.into()
+    }
+
+    /// Returns the existential path `∃f`, the unary function
+    /// that is `true` for the values returned by the function.
+    pub fn existential_path(&self) -> Option<Expr> {
+        let table = self.truth_table()?;
+        Some(Expr::if_(
+            Expr::constant_value(table.contains(&true)),
+            Expr::constant_value(table.contains(&false))
+        ))
+    }
+
+    fn constant_value(val: bool) -> Expr {
+        if val {Expr::True} else {Expr::False}
+    }
+}
+
+/// Builds the table of symmetric paths `f[p] <=> g` by unary functions.
+pub fn sympath_table(max_arity: usize) -> Table {
+    let mut rows = vec![];
+    for n in 0..max_arity + 1 {
+        for f in Expr::all(n) {
+            for p in Expr::unary() {
+                let g = f.sympath(&p).map(|g| g.name()).unwrap_or_else(|| "{}".into());
+                rows.push(vec![f.name(), p.name(), g]);
+            }
+        }
+    }
+    Table::new(&["function", "path", "result"], rows)
+}
+
+/// Builds the table of asymmetric paths `f[p0 -> ... -> pn] <=> g` by unary functions.
+pub fn asympath_table(max_arity: usize) -> Table {
+    let rows = Expr::asympath_table(max_arity).into_iter().map(|entry| {
+        let ps: Vec<String> = entry.ps.iter().map(|p| p.name()).collect();
+        let g = entry.g.map(|g| g.name()).unwrap_or_else(|_| "{}".into());
+        vec![entry.f.name(), ps.join(" -> "), g]
+    }).collect();
+    Table::new(&["function", "paths", "result"], rows)
+}
+
+/// Builds the table of existential paths `∃f`.
+pub fn existential_table(max_arity: usize) -> Table {
+    let mut rows = vec![];
+    for n in 0..max_arity + 1 {
+        for f in Expr::all(n) {
+            rows.push(vec![f.name(), f.existential_path().unwrap().name()]);
+        }
+    }
+    Table::new(&["function", "existential path"], rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names() {
+        let mut names: Vec<String> = Expr::all(2).iter().map(|f| f.name()).collect();
+        assert_eq!(Expr::and().name(), "and");
+        assert_eq!(Expr::parse("a & !b").unwrap().name(), "exc");
+        assert_eq!(Expr::false_n(2).name(), "false_2");
+        assert_eq!(Expr::true_n(3).name(), "true_3");
+        assert_eq!(Expr::parse("a & b & c").unwrap().name(), "00000001");
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), 16);
+    }
+
+    #[test]
+    fn existential_paths() {
+        // ∃false_1 <=> not
+        assert_eq!(Expr::false_1().existential_path(), Some(Expr::not()));
+        // ∃true_1 <=> id
+        assert_eq!(Expr::true_1().existential_path(), Some(Expr::id()));
+        // ∃and <=> true_1
+        assert_eq!(Expr::and().existential_path(), Some(Expr::true_1()));
+    }
+
+    #[test]
+    fn tables() {
+        let sym = sympath_table(2);
+        assert!(sym.rows.contains(&vec!["and".into(), "not".into(), "or".into()]));
+        assert!(sym.rows.contains(&vec!["xor".into(), "not".into(), "eq".into()]));
+        assert!(sym.rows.contains(&vec!["nexc".into(), "not".into(), "rexc".into()]));
+
+        let asym = asympath_table(1);
+        assert!(asym.rows.contains(&vec!["not".into(), "id -> not".into(), "id".into()]));
+        assert!(asym.rows.contains(&vec!["not".into(), "false_1 -> not".into(), "{}".into()]));
+
+        let ex = existential_table(1);
+        assert_eq!(ex.to_csv(), "\
+function,existential path
+false,not
+false_1,not
+id,true_1
+not,true_1
+true,id
+true_1,id
+");
+        assert_eq!(existential_table(0).to_markdown(), "\
+| function | existential path |
+|---|---|
+| false | not |
+| true | id |
+");
+    }
+}
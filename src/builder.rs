@@ -0,0 +1,262 @@
+//! A builder for programs.
+//!
+//! Assigns function and instruction indices,
+//! such that programs can be constructed without writing instructions by hand.
+//!
+//! Patterns and expressions are parsed with the same rules as source,
+//! e.g. `[true] true`, `X`, `_` or `not(X)`.
+//! Names starting with an upper case letter are variables.
+//! Every `_` is a new variable.
+
+use std::sync::Arc;
+
+use interpreter::Op;
+use program::Program;
+use term::Term;
+
+/// Builds a program.
+#[derive(Clone, Debug, Default)]
+pub struct ProgramBuilder {
+    program: Program,
+    /// The last declared function.
+    last: Option<usize>,
+    /// The first error.
+    error: Option<String>,
+}
+
+impl ProgramBuilder {
+    /// Creates a new program builder.
+    pub fn new() -> ProgramBuilder {
+        ProgramBuilder::default()
+    }
+
+    /// Declares a type `fn ty() -> ty;`.
+    ///
+    /// Following clauses are an error until the next function.
+    pub fn ty(mut self, name: &str) -> ProgramBuilder {
+        if self.error.is_some() {return self};
+        let index = self.declare(name);
+        self.last = None;
+        self.program.add_case(index, &[], &[Op::FnRef(index)]);
+        self
+    }
+
+    /// Declares a member `name: ty;`, which is the same as `fn name(ty) -> name;`.
+    ///
+    /// The type is declared if it does not exist.
+    /// Following clauses are an error until the next function.
+    pub fn member(mut self, name: &str, ty: &str) -> ProgramBuilder {
+        if self.error.is_some() {return self};
+        if self.program.find(ty).is_none() {
+            self = self.ty(ty);
+        }
+        let ty = self.program.find(ty).unwrap();
+        let index = self.declare(name);
+        self.last = None;
+        self.program.add_case(index, &[Op::FnRef(ty)], &[Op::FnRef(index)]);
+        self
+    }
+
+    /// Declares a function with argument types and return type.
+    ///
+    /// Following clauses are added to this function.
+    pub fn func(mut self, name: &str, args: &[&str], ret: &str) -> ProgramBuilder {
+        if self.error.is_some() {return self};
+        let index = self.declare(name);
+        self.last = Some(index);
+        self.clause(args, ret)
+    }
+
+    /// Adds a clause to the last declared function, e.g. `[:] (true) -> false;`.
+    pub fn clause(mut self, args: &[&str], ret: &str) -> ProgramBuilder {
+        if self.error.is_some() {return self};
+        let res = match self.last {
+            None => Err("Expected function before clause".into()),
+            Some(f) => self.case(f, args, ret),
+        };
+        if let Err(err) = res {
+            self.error = Some(err);
+        }
+        self
+    }
+
+    /// Builds the program.
+    pub fn program(self) -> Result<Program, String> {
+        match self.error {
+            None => Ok(self.program),
+            Some(err) => Err(err),
+        }
+    }
+
+    /// Builds functions and instructions.
+    pub fn build(self) -> Result<(Vec<Op>, Vec<Op>), String> {
+        let program = self.program()?;
+        Ok((program.fns, program.ops))
+    }
+
+    /// Declares a new name, or returns the index of an existing one.
+    fn declare(&mut self, name: &str) -> usize {
+        match self.program.find(name) {
+            Some(index) => index,
+            None => {
+                let index = self.program.fns.len();
+                self.program.names.push((Arc::new(name.into()), index));
+                index
+            }
+        }
+    }
+
+    /// Adds a function case.
+    fn case(&mut self, f: usize, args: &[&str], ret: &str) -> Result<(), String> {
+        let mut vars = vec![];
+        let mut fn_args = vec![];
+        for arg in args {
+            pattern(&self.program, &Term::parse(arg)?, &mut vars, &mut fn_args)?;
+        }
+        let mut ops = vec![];
+        expr(&self.program, &Term::parse(ret)?, &vars, &mut ops)?;
+        self.program.add_case(f, &fn_args, &ops);
+        Ok(())
+    }
+}
+
+/// Lowers a pattern, e.g. `[true] true` or `X`.
+fn pattern(
+    program: &Program,
+    term: &Term,
+    vars: &mut Vec<Arc<String>>,
+    ops: &mut Vec<Op>
+) -> Result<(), String> {
+    match *term {
+        Term::Path(ref p, ref term) => {
+            ops.push(Op::Path);
+            ops.push(Op::FnRef(find(program, p)?));
+            pattern(program, term, vars, ops)
+        }
+        Term::App(ref name, ref args) => {
+            if !args.is_empty() {
+                return Err(format!("Expected pattern, found application of `{}`", name));
+            }
+            if ::is_var(name) {
+                let index = match vars.iter().position(|v| v == name) {
+                    Some(index) if &***name != "_" => index,
+                    _ => {
+                        vars.push(name.clone());
+                        vars.len() - 1
+                    }
+                };
+                ops.push(Op::Var(index));
+            } else {
+                ops.push(Op::FnRef(find(program, name)?));
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Lowers an expression, e.g. `[false] false` or `not(X)`.
+fn expr(
+    program: &Program,
+    term: &Term,
+    vars: &[Arc<String>],
+    ops: &mut Vec<Op>
+) -> Result<(), String> {
+    match *term {
+        Term::Path(ref p, ref term) => {
+            ops.push(Op::Path);
+            ops.push(Op::FnRef(find(program, p)?));
+            expr(program, term, vars, ops)
+        }
+        Term::App(ref name, ref args) => {
+            if ::is_var(name) {
+                match vars.iter().position(|v| v == name) {
+                    Some(index) if args.is_empty() && &***name != "_" => {
+                        ops.push(Op::Var(index));
+                    }
+                    _ => { return Err(format!("Could not find variable `{}`", name)); }
+                }
+            } else {
+                if !args.is_empty() {ops.push(Op::Call)};
+                ops.push(Op::FnRef(find(program, name)?));
+                for arg in args {
+                    expr(program, arg, vars, ops)?;
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+fn find(program: &Program, name: &str) -> Result<usize, String> {
+    program.find(name).ok_or_else(|| format!("Could not find `{}`", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use interpreter::convert;
+
+    fn bool_builder() -> ProgramBuilder {
+        ProgramBuilder::new()
+            .member("true", "bool")
+            .member("false", "bool")
+            .func("not", &["bool"], "bool")
+            .clause(&["[true] true"], "[false] false")
+            .clause(&["[false] false"], "[true] true")
+    }
+
+    #[test]
+    fn not_not_false() {
+        let program = bool_builder().program().unwrap();
+        assert_eq!(program.eval_str("not(not([false] false))").unwrap(),
+                   Term::parse("[false] false").unwrap());
+    }
+
+    #[test]
+    fn call_in_return() {
+        let program = bool_builder()
+            .func("id", &["bool"], "bool")
+            .clause(&["X"], "not(not(X))")
+            .program().unwrap();
+        let id = program.find("id").unwrap();
+        let value = Term::parse("[true] true").unwrap();
+        assert_eq!(program.call(id, ::std::slice::from_ref(&value)).unwrap(), value);
+    }
+
+    #[test]
+    fn same_as_convert() {
+        use piston_meta::parse;
+
+        let rules = ::syntax_rules();
+        let source = "
+fn bool() -> bool;
+true: bool;
+false: bool;
+fn eq(bool, bool) -> bool;
+[:] (X, X) -> true;
+[:] ([eq] _, _) -> false;
+        ";
+        let mut data = vec![];
+        parse(&rules, source, &mut data).unwrap();
+        assert_eq!(convert(&data, &mut vec![]).unwrap(), ProgramBuilder::new()
+            .ty("bool")
+            .member("true", "bool")
+            .member("false", "bool")
+            .func("eq", &["bool", "bool"], "bool")
+            .clause(&["X", "X"], "true")
+            .clause(&["[eq] _", "_"], "false")
+            .build().unwrap());
+    }
+
+    #[test]
+    fn errors() {
+        assert!(ProgramBuilder::new().clause(&[], "true").build().is_err());
+        assert!(ProgramBuilder::new().func("f", &["bool"], "bool").build().is_err());
+        assert!(bool_builder().clause(&["X"], "Y").build().is_err());
+        assert!(bool_builder().clause(&["not(X)"], "X").build().is_err());
+        assert!(bool_builder().clause(&["X"], "not(").build().is_err());
+        // A clause after a member does not add a case to the previous function.
+        assert_eq!(bool_builder().member("maybe", "bool").clause(&["true"], "false").build(),
+                   Err("Expected function before clause".into()));
+    }
+}
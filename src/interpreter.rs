@@ -5,7 +5,7 @@ use range::Range;
 use std::sync::Arc;
 
 /// Instructions.
-#[derive(PartialEq, Debug, Copy, Clone)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
 pub enum Op {
     /// Treats the next instruction as a function call.
     Call,
@@ -23,179 +23,294 @@ pub enum Op {
     End,
 }
 
-/// Evaluates a program.
-pub fn eval(fns: &[Op], ops: &[Op], st: &mut Vec<Op>) {
+/// Evaluates a program, returning an error if no function case matches.
+///
+/// The result is left as raw stack contents, see `Program::eval_str` for terms.
+pub(crate) fn try_eval(fns: &[Op], ops: &[Op], st: &mut Vec<Op>) -> Result<(), String> {
+    run(fns, ops, ops.len(), None, st)
+}
+
+/// Calls the function on top of the stack.
+///
+/// The arguments must be on the stack below the function.
+/// Returns an error if no function case matches.
+/// The result is left as raw stack contents, see `Program::call` for terms.
+pub(crate) fn call(fns: &[Op], ops: &[Op], st: &mut Vec<Op>) -> Result<(), String> {
+    let f = match st.last() {
+        Some(&Op::FnRef(f)) => f,
+        x => { return Err(format!("Expected `FnRef`, found `{:?}`", x)); }
+    };
+    run(fns, ops, 0, Some(f), st)
+}
+
+/// Runs instructions below `call`, starting with an optional function call.
+///
+/// When a function is given, its name and arguments must be on the stack.
+fn run(
+    fns: &[Op],
+    ops: &[Op],
+    mut call: usize,
+    mut f: Option<usize>,
+    st: &mut Vec<Op>
+) -> Result<(), String> {
     let mut vars: Vec<Op> = Vec::new();
-    let mut calls: Vec<usize> = Vec::new();
-    let mut call = ops.len();
+    // Stores the instruction to continue and the length of variable stack.
+    let mut calls: Vec<(usize, usize)> = Vec::new();
     'e: loop {
-        let mut f = None;
-        // Push instructions.
-        for op in ops[0..call].iter().rev() {
-            match op {
-                &Op::Var(x) => {
-                    // Find variable.
-                    let mut v_index = None;
-                    for i in (0 .. vars.len()).rev() {
-                        if &vars[i] == &Op::Var(x) {
-                            v_index = Some(i);
-                            break;
+        if f.is_none() {
+            // Push instructions.
+            for op in ops[0..call].iter().rev() {
+                match *op {
+                    Op::Var(x) => {
+                        // Find variable.
+                        call -= 1;
+                        let v_index = vars.iter().rposition(|v| v == &Op::Var(x));
+                        let start = 1 + match v_index {
+                            None => {
+                                return Err("Count not find variable index".into());
+                            }
+                            Some(i) => i,
+                        };
+                        let end = start + vars[start..].iter()
+                            .take_while(|&v| v != &Op::End).count();
+                        // Push variable instructions.
+                        for v in vars[start .. end].iter().rev() {
+                            st.push(*v);
                         }
                     }
-                    let start = 1 + v_index.expect("Count not find variable index");
-                    let end = start + vars[start..].iter()
-                        .take_while(|&v| v != &Op::End).count();
-                    // Push variable instructions.
-                    for v in vars[start .. end].iter().rev() {
-                        st.push(*v);
+                    Op::End => {
+                        // Continue previous call.
+                        // Remove variables of the returning function.
+                        call = match calls.pop() {
+                            None => { return Ok(()); }
+                            Some((call, n)) => {
+                                vars.truncate(n);
+                                call
+                            }
+                        };
+                        continue 'e;
+                    }
+                    Op::Call => {
+                        // Call a function.
+                        call -= 1;
+                        f = match st.last() {
+                            Some(&Op::FnRef(f)) => Some(f),
+                            x => {
+                                return Err(format!("Expected `FnRef`, found `{:?}`", x));
+                            }
+                        };
+                        break;
+                    }
+                    x => {
+                        // Push instruction.
+                        call -= 1;
+                        st.push(x);
                     }
-                }
-                &Op::End => {
-                    // Continue previous call.
-                    call = match calls.pop() {
-                        None => { return; }
-                        Some(call) => call,
-                    };
-                    continue 'e;
-                }
-                &Op::Call => {
-                    // Call a function.
-                    call -= 1;
-                    f = match st.last() {
-                        Some(&Op::FnRef(f)) => Some(f),
-                        x => { panic!("Expected `FnRef`, found `{:?}`", x); }
-                    };
-                    break;
-                }
-                x => {
-                    // Push instruction.
-                    call -= 1;
-                    st.push(*x);
                 }
             }
         }
 
-        if let Some(mut f) = f {
-            // The length of variable stack before pusing function variables.
-            let var_len = vars.len();
-            // Pattern match function.
-            'f: loop {
-                // An index of the instruction on the stack to match against.
-                let mut j = st.len();
-                for (i, fi) in fns[f..].iter().enumerate() {
-                    match fi {
-                        &Op::End => {
-                            panic!("Function does not return a value");
-                        }
-                        &Op::OpRef(o) => {
-                            // Remove values and call function.
-                            st.truncate(j);
-                            calls.push(call);
-                            call = o + 1;
-                            continue 'e;
-                        }
-                        _ => {}
+        let mut f = match f.take() {
+            // Reached the start of instructions.
+            None => { return Ok(()); }
+            Some(f) => f,
+        };
+        // The length of variable stack before pusing function variables.
+        let var_len = vars.len();
+        // A copy of the stack before path functions replaced arguments.
+        let mut st_backup: Option<Vec<Op>> = None;
+        // Pattern match function.
+        'f: loop {
+            // An index of the instruction on the stack to match against.
+            let mut j = st.len();
+            // An index of the instruction in the function signature.
+            let mut i = 0;
+            loop {
+                match fns.get(f + i) {
+                    None | Some(&Op::End) => {
+                        return Err("Function does not return a value".into());
                     }
-                    if j == 0 {
-                        panic!("Stack is shorter than function signature");
+                    Some(&Op::OpRef(o)) => {
+                        // Remove values and call function.
+                        st.truncate(j);
+                        calls.push((call, var_len));
+                        call = o + 1;
+                        continue 'e;
                     }
-                    if let &Op::Var(x) = fi {
+                    _ => {}
+                }
+                let matched = match fns[f + i] {
+                    // The stack is shorter than the function signature.
+                    _ if j == 0 => false,
+                    Op::Var(x) => {
                         // The function signature contains a variable.
+                        let n = value_len(st, j);
 
                         // Look for existing variable.
-                        let mut existing_var = None;
-                        for v in vars[var_len..].iter().rev() {
-                            match v {
-                                &Op::Var(y) if x == y => {
-                                    existing_var = Some(y);
-                                }
-                                _ => {}
-                            }
-                        }
+                        let existing_var = vars[var_len..].iter()
+                            .rposition(|v| v == &Op::Var(x))
+                            .map(|k| var_len + k);
 
-                        if let Some(k) = existing_var {
+                        let equal = if let Some(k) = existing_var {
                             // Check for equality with existing variable.
-                            let mut equal = true;
-                            for v in &vars[k + 1..] {
-                                match v {
-                                    &Op::End => { break; }
-                                    x if x == &st[j - 1] => { j -= 1; continue; }
-                                    _ => { equal = false; break; }
-                                }
-                            }
-
-                            if !equal {
-                                // The pattern match failed.
-                                // Try next function.
-                                // Roll back variables.
-                                vars.truncate(var_len);
-                                for (k, fi) in fns[f + i..].iter().enumerate() {
-                                    match fi {
-                                        &Op::End | &Op::OpRef(_) => {
-                                            f = f + i + k + 1;
-                                            if f >= fns.len() {
-                                                panic!("No function matched (end)");
-                                            }
-                                            continue 'f;
-                                        }
-                                        _ => {}
-                                    }
-                                }
-
-                                panic!("Expected `End` or `OpRef` after function signature");
-                            }
+                            let value = vars[k + 1..].iter()
+                                .take_while(|&v| v != &Op::End);
+                            value.eq(st[j - n..j].iter().rev())
                         } else {
                             // Push new variable.
-                            let mut count_down: usize = 1;
                             vars.push(Op::Var(x));
-                            while count_down > 0 && j > 0 {
-                                j -= 1;
-                                vars.push(st[j]);
-                                if let &Op::Path = &st[j] {
-                                    count_down += 1;
-                                } else {
-                                    count_down -= 1;
-                                }
-                            }
+                            vars.extend(st[j - n..j].iter().rev());
                             vars.push(Op::End);
+                            true
+                        };
+                        if equal {
+                            j -= n;
+                            i += 1;
                         }
-                        continue;
+                        equal
                     }
-                    j -= 1;
-                    if fi != &st[j] {
-                        // The pattern match failed.
-                        if i == 0 {
-                            panic!("No function matched (failed to match name)");
-                        }
-                        // Try next function.
-                        // Roll back variables.
-                        vars.truncate(var_len);
-                        for (k, fi) in fns[f + i..].iter().enumerate() {
-                            match fi {
-                                &Op::End | &Op::OpRef(_) => {
-                                    f = f + i + k + 1;
-                                    if f >= fns.len() {
-                                        panic!("No function matched (end)");
-                                    }
-                                    continue 'f;
-                                }
-                                _ => {}
+                    Op::Path if st[j - 1] != Op::Path => {
+                        // The argument has no path,
+                        // so the path function is called on the argument.
+                        let p = match fns.get(f + i + 1) {
+                            Some(&Op::FnRef(p)) => p,
+                            x => {
+                                return Err(format!(
+                                    "Expected `FnRef` after `Path`, found `{:?}`", x));
+                            }
+                        };
+                        let start = j - value_len(st, j);
+                        let mut res = st[start..j].to_vec();
+                        res.push(Op::FnRef(p));
+                        let called = match run(fns, ops, 0, Some(p), &mut res) {
+                            Ok(()) => true,
+                            // The path function is not defined for the argument.
+                            Err(ref err) if is_no_match(err) => false,
+                            Err(err) => { return Err(err); }
+                        };
+                        if called {
+                            // Replace argument with the result,
+                            // and match the result against the rest of the pattern.
+                            if st_backup.is_none() {
+                                st_backup = Some(st.clone());
                             }
+                            let tail = st.split_off(j);
+                            st.truncate(start);
+                            st.extend(res);
+                            j = st.len();
+                            st.extend(tail);
+                            i += 2;
+                            true
+                        } else {
+                            false
+                        }
+                    }
+                    fi => {
+                        j -= 1;
+                        if fi == st[j] {
+                            i += 1;
+                            true
+                        } else {
+                            false
                         }
+                    }
+                };
 
-                        panic!("Expected `End` or `OpRef` after function signature");
+                if !matched {
+                    // The pattern match failed.
+                    if i == 0 {
+                        return Err("No function matched (failed to match name)".into());
+                    }
+                    // Try next function.
+                    // Roll back variables and stack.
+                    vars.truncate(var_len);
+                    if let Some(ref backup) = st_backup {
+                        st.clone_from(backup);
                     }
+                    f = next_clause(fns, f + i)?;
+                    continue 'f;
                 }
             }
         }
     }
 }
 
+/// Returns `true` if the error is from no function case matching.
+fn is_no_match(err: &str) -> bool {
+    err.starts_with("No function matched")
+}
+
+/// Returns the number of stack items of the value ending at `j`.
+fn value_len(st: &[Op], j: usize) -> usize {
+    let mut count_down: usize = 1;
+    let mut n = 0;
+    while count_down > 0 && n < j {
+        n += 1;
+        if let Op::Path = st[j - n] {
+            count_down += 1;
+        } else {
+            count_down -= 1;
+        }
+    }
+    n
+}
+
+/// Finds the start of the next function clause after `pos`.
+fn next_clause(fns: &[Op], pos: usize) -> Result<usize, String> {
+    for (k, fi) in fns[pos..].iter().enumerate() {
+        match *fi {
+            Op::End | Op::OpRef(_) => {
+                let mut next = pos + k + 1;
+                // Skip end of function clause.
+                if let (&Op::OpRef(_), Some(&Op::End)) = (fi, fns.get(next)) {
+                    next += 1;
+                }
+                if next >= fns.len() {
+                    return Err("No function matched (end)".into());
+                }
+                return Ok(next);
+            }
+            _ => {}
+        }
+    }
+
+    Err("Expected `End` or `OpRef` after function signature".into())
+}
+
 /// Converts from meta data to function and instruction stack.
+///
+/// Names starting with an upper case letter are variables.
+/// Every `_` is a new variable.
+/// A function without name, e.g. `[:] (bart) -> homer;`,
+/// adds a case to the previous function.
+/// A member declaration `a: b;` is the same as `fn a(b) -> a;`.
+/// A path declaration, e.g. `fn add [is_zero] (bool, bool) -> bool;`
+/// or `fn f [p0 x p1 -> q] (...) -> ...;`, declares a function `add[is_zero]`
+/// with an extra case `FnPath` followed by the function and its paths.
+#[allow(clippy::result_unit_err)]
 pub fn convert(
-    mut data: &[Range<MetaData>],
+    data: &[Range<MetaData>],
     ignored: &mut Vec<Range>
 ) -> Result<(Vec<Op>, Vec<Op>), ()> {
+    let mut fns = vec![];
+    let mut ops = vec![];
+    let mut names = vec![];
+    convert_extend(data, &mut fns, &mut ops, &mut names, ignored)?;
+    Ok((fns, ops))
+}
+
+/// Converts from meta data, extending existing functions and instructions.
+///
+/// The names of declared functions are stored with their function index.
+/// Names that are already declared can be used by the meta data.
+#[allow(clippy::result_unit_err)]
+pub fn convert_extend(
+    mut data: &[Range<MetaData>],
+    fns: &mut Vec<Op>,
+    ops: &mut Vec<Op>,
+    names: &mut Vec<(Arc<String>, usize)>,
+    ignored: &mut Vec<Range>
+) -> Result<(), ()> {
     use piston_meta::bootstrap::{ end_node, ignore, start_node, update,
         meta_string };
 
@@ -227,19 +342,37 @@ pub fn convert(
     }
 
     fn read_ret(
-        mut data: &[Range<MetaData>],
-        mut offset: usize,
-        _fns: &mut Vec<Op>,
+        data: &[Range<MetaData>],
+        offset: usize,
         ops: &mut Vec<Op>,
         state: &ConvertState,
-        names: &mut Vec<(Arc<String>, usize)>,
+        names: &[(Arc<String>, usize)],
+        vars: &[Arc<String>],
         ignored: &mut Vec<Range>
     ) -> Result<(Range, ConvertState), ()> {
-        let mut new_state = state.clone();
-        let start_offset = offset;
-        let node = "ret";
-        let range = try!(start_node(node, data, offset));
+        let mut new_state = *state;
         let mut new_ops = vec![];
+        let range = read_expr("ret", data, offset, &mut new_ops, names, vars, ignored)?;
+        new_state = push_op(&new_state, ops, Op::End);
+        for op in new_ops {
+            new_state = push_op(&new_state, ops, op);
+        }
+        Ok((range, new_state))
+    }
+
+    // Reads an expression as instructions in the order they are written,
+    // e.g. `not([false] X)` is `Call, FnRef(not), Path, FnRef(false), Var(0)`.
+    fn read_expr(
+        node: &str,
+        mut data: &[Range<MetaData>],
+        mut offset: usize,
+        new_ops: &mut Vec<Op>,
+        names: &[(Arc<String>, usize)],
+        vars: &[Arc<String>],
+        ignored: &mut Vec<Range>
+    ) -> Result<Range, ()> {
+        let start_offset = offset;
+        let range = start_node(node, data, offset)?;
         update(range, &mut data, &mut offset);
         loop {
             if let Ok(range) = end_node(node, data, offset) {
@@ -255,40 +388,66 @@ pub fn convert(
                 new_ops.push(Op::FnRef(index));
             } else if let Ok((range, val)) = meta_string("ns_name", data, offset) {
                 update(range, &mut data, &mut offset);
-                let index = match find_name(val, names) {
-                    None => { return Err(()); }
-                    Some(index) => index,
-                };
-                new_ops.push(Op::FnRef(index));
+                if ::is_var(&val) {
+                    // The variable must be bound by the arguments.
+                    let index = match vars.iter().position(|v| v == &val) {
+                        None => { return Err(()); }
+                        Some(index) => index,
+                    };
+                    new_ops.push(Op::Var(index));
+                } else {
+                    let index = match find_name(val, names) {
+                        None => { return Err(()); }
+                        Some(index) => index,
+                    };
+                    new_ops.push(Op::FnRef(index));
+                }
+            } else if let Ok(range) = start_node("repeated_arguments", data, offset) {
+                update(range, &mut data, &mut offset);
+                // Call the function with the arguments.
+                match new_ops.pop() {
+                    Some(Op::FnRef(f)) => {
+                        new_ops.push(Op::Call);
+                        new_ops.push(Op::FnRef(f));
+                    }
+                    _ => { return Err(()); }
+                }
+                loop {
+                    if let Ok(range) = end_node("repeated_arguments", data, offset) {
+                        update(range, &mut data, &mut offset);
+                        break;
+                    } else if let Ok(range) = read_expr(
+                        "arg", data, offset, new_ops, names, vars, ignored
+                    ) {
+                        update(range, &mut data, &mut offset);
+                    } else {
+                        let range = ignore(data, offset);
+                        update(range, &mut data, &mut offset);
+                        ignored.push(range);
+                    }
+                }
             } else {
                 let range = ignore(data, offset);
                 update(range, &mut data, &mut offset);
                 ignored.push(range);
             }
         }
-
-        new_ops.push(Op::End);
-
-        // Reverse instructions.
-        for op in new_ops.into_iter().rev() {
-            new_state = push_op(&new_state, ops, op);
-        }
-        Ok((Range::new(start_offset, offset - start_offset), new_state))
+        Ok(Range::new(start_offset, offset - start_offset))
     }
 
     fn read_arg(
         mut data: &[Range<MetaData>],
         mut offset: usize,
         fns: &mut Vec<Op>,
-        _ops: &mut Vec<Op>,
         state: &ConvertState,
-        names: &mut Vec<(Arc<String>, usize)>,
+        names: &[(Arc<String>, usize)],
+        vars: &mut Vec<Arc<String>>,
         ignored: &mut Vec<Range>
     ) -> Result<(Range, ConvertState), ()> {
-        let mut new_state = state.clone();
+        let mut new_state = *state;
         let start_offset = offset;
         let node = "arg";
-        let range = try!(start_node(node, data, offset));
+        let range = start_node(node, data, offset)?;
         update(range, &mut data, &mut offset);
         loop {
             if let Ok(range) = end_node(node, data, offset) {
@@ -304,11 +463,62 @@ pub fn convert(
                 new_state = push_fn(&new_state, fns, Op::FnRef(index));
             } else if let Ok((range, val)) = meta_string("ns_name", data, offset) {
                 update(range, &mut data, &mut offset);
-                let index = match find_name(val, names) {
+                if ::is_var(&val) {
+                    let index = match vars.iter().position(|v| v == &val) {
+                        Some(index) if &**val != "_" => index,
+                        _ => {
+                            vars.push(val);
+                            vars.len() - 1
+                        }
+                    };
+                    new_state = push_fn(&new_state, fns, Op::Var(index));
+                } else {
+                    let index = match find_name(val, names) {
+                        None => { return Err(()); }
+                        Some(index) => index,
+                    };
+                    new_state = push_fn(&new_state, fns, Op::FnRef(index));
+                }
+            } else {
+                let range = ignore(data, offset);
+                update(range, &mut data, &mut offset);
+                ignored.push(range);
+            }
+        }
+
+        Ok((Range::new(start_offset, offset - start_offset), new_state))
+    }
+
+    fn read_asym_path(
+        mut data: &[Range<MetaData>],
+        mut offset: usize,
+        names: &[(Arc<String>, usize)],
+        arg_paths: &mut Vec<(Arc<String>, usize)>,
+        ret_path: &mut Option<(Arc<String>, usize)>,
+        ignored: &mut Vec<Range>
+    ) -> Result<Range, ()> {
+        let start_offset = offset;
+        let node = "asym_path";
+        let range = start_node(node, data, offset)?;
+        update(range, &mut data, &mut offset);
+        loop {
+            if let Ok(range) = end_node(node, data, offset) {
+                update(range, &mut data, &mut offset);
+                break;
+            } else if let Ok((range, val)) = meta_string("arg_path", data, offset) {
+                update(range, &mut data, &mut offset);
+                let index = match find_name(val.clone(), names) {
                     None => { return Err(()); }
                     Some(index) => index,
                 };
-                new_state = push_fn(&new_state, fns, Op::FnRef(index));
+                arg_paths.push((val, index));
+            } else if let Ok((range, val)) = meta_string("ret_path", data, offset) {
+                update(range, &mut data, &mut offset);
+                let index = match find_name(val.clone(), names) {
+                    None => { return Err(()); }
+                    Some(index) => index,
+                };
+                *ret_path = Some((val, index));
             } else {
                 let range = ignore(data, offset);
                 update(range, &mut data, &mut offset);
@@ -316,7 +526,7 @@ pub fn convert(
             }
         }
 
-        Ok((Range::new(start_offset, offset - start_offset), new_state))
+        Ok(Range::new(start_offset, offset - start_offset))
     }
 
     fn read_fn(
@@ -328,33 +538,157 @@ pub fn convert(
         names: &mut Vec<(Arc<String>, usize)>,
         ignored: &mut Vec<Range>
     ) -> Result<(Range, ConvertState), ()> {
-        let mut new_state = state.clone();
+        let mut new_state = *state;
         let start_offset = offset;
         let node = "fn";
-        let range = try!(start_node(node, data, offset));
+        let range = start_node(node, data, offset)?;
         update(range, &mut data, &mut offset);
+        // Variables of the function case.
+        let mut vars = vec![];
+        // The name of the function.
+        let mut name: Option<Arc<String>> = None;
+        // The function index, set when the name is pushed.
+        let mut index: Option<usize> = None;
+        // The base function when declaring a path, e.g. `fn add [is_zero] ...`.
+        let mut base: Option<usize> = None;
+        // The paths of arguments, empty for symmetric paths.
+        let mut arg_paths: Vec<(Arc<String>, usize)> = vec![];
+        // The path of return value.
+        let mut ret_path: Option<(Arc<String>, usize)> = None;
+        // The number of arguments.
+        let mut args = 0;
         loop {
             if let Ok(range) = end_node(node, data, offset) {
                 update(range, &mut data, &mut offset);
                 break;
-            } else if let Ok((range, name)) = meta_string("name", data, offset) {
+            } else if let Ok((range, val)) = meta_string("name", data, offset) {
+                update(range, &mut data, &mut offset);
+                name = Some(val);
+            } else if let Ok((range, val)) = meta_string("path", data, offset) {
                 update(range, &mut data, &mut offset);
-                let index = match find_name(name.clone(), names) {
-                    None => { fns.len() }
+                let index = match find_name(val.clone(), names) {
+                    None => { return Err(()); }
                     Some(index) => index,
                 };
-                names.push((name, index));
-                new_state = push_fn(&new_state, fns, Op::FnRef(index));
-            } else if let Ok((range, state)) = read_arg(
-                    data, offset, fns, ops, &new_state, names, ignored
+                ret_path = Some((val, index));
+            } else if let Ok(range) = read_asym_path(
+                    data, offset, names, &mut arg_paths, &mut ret_path, ignored
                 ) {
                 update(range, &mut data, &mut offset);
-                new_state = state;
-            } else if let Ok((range, state)) = read_ret(
-                    data, offset, fns, ops, &new_state, names, ignored
+            } else {
+                if index.is_none() {
+                    // Push the name before the arguments.
+                    let mut name = match name {
+                        None => { return Err(()); }
+                        Some(ref name) => name.clone(),
+                    };
+                    if let Some((ref p, _)) = ret_path {
+                        // Declare a path of a function, e.g. `add[is_zero]`.
+                        base = find_name(name.clone(), names);
+                        if base.is_none() { return Err(()); }
+                        name = Arc::new(if arg_paths.is_empty() {
+                            format!("{}[{}]", name, p)
+                        } else {
+                            let arg_paths: Vec<&str> = arg_paths.iter()
+                                .map(|p| &**p.0).collect();
+                            format!("{}[{} -> {}]", name, arg_paths.join(" x "), p)
+                        });
+                    }
+                    let new_index = if name.is_empty() {
+                        // Add case to the previous function.
+                        match names.last() {
+                            None => { return Err(()); }
+                            Some(&(_, index)) => index,
+                        }
+                    } else {
+                        let index = match find_name(name.clone(), names) {
+                            None => { new_state.0 }
+                            Some(index) => index,
+                        };
+                        names.push((name, index));
+                        index
+                    };
+                    new_state = push_fn(&new_state, fns, Op::FnRef(new_index));
+                    index = Some(new_index);
+                }
+
+                if let Ok((range, state)) = read_arg(
+                    data, offset, fns, &new_state, names, &mut vars, ignored
+                ) {
+                    update(range, &mut data, &mut offset);
+                    new_state = state;
+                    args += 1;
+                } else if let Ok((range, state)) = read_ret(
+                    data, offset, ops, &new_state, names, &vars, ignored
+                ) {
+                    update(range, &mut data, &mut offset);
+                    new_state = push_fn(&state, fns, Op::OpRef(ops.len() - 1));
+                } else {
+                    let range = ignore(data, offset);
+                    update(range, &mut data, &mut offset);
+                    ignored.push(range);
+                }
+            }
+        }
+
+        new_state = push_fn(&new_state, fns, Op::End);
+
+        if let (Some(index), Some(base), Some((_, q))) = (index, base, ret_path) {
+            // Declare the path with a case that never matches arguments,
+            // e.g. `FnRef(add[is_zero]), FnPath, FnRef(add), ...paths`.
+            if !arg_paths.is_empty() && arg_paths.len() != args { return Err(()); }
+            new_state = push_fn(&new_state, fns, Op::FnRef(index));
+            new_state = push_fn(&new_state, fns, Op::FnPath);
+            new_state = push_fn(&new_state, fns, Op::FnRef(base));
+            for i in 0..args {
+                let p = if arg_paths.is_empty() { q } else { arg_paths[i].1 };
+                new_state = push_fn(&new_state, fns, Op::FnRef(p));
+            }
+            new_state = push_fn(&new_state, fns, Op::FnRef(q));
+            new_state = push_fn(&new_state, fns, Op::End);
+        }
+        Ok((Range::new(start_offset, offset - start_offset), new_state))
+    }
+
+    fn read_member(
+        mut data: &[Range<MetaData>],
+        mut offset: usize,
+        fns: &mut Vec<Op>,
+        ops: &mut Vec<Op>,
+        state: &ConvertState,
+        names: &mut Vec<(Arc<String>, usize)>,
+        ignored: &mut Vec<Range>
+    ) -> Result<(Range, ConvertState), ()> {
+        let mut new_state = *state;
+        let start_offset = offset;
+        let node = "member";
+        let range = start_node(node, data, offset)?;
+        update(range, &mut data, &mut offset);
+        let range = start_node("member_lambda", data, offset)?;
+        update(range, &mut data, &mut offset);
+        let range = start_node("arg", data, offset)?;
+        update(range, &mut data, &mut offset);
+        let (range, name) = meta_string("ns_name", data, offset)?;
+        update(range, &mut data, &mut offset);
+        let range = end_node("arg", data, offset)?;
+        update(range, &mut data, &mut offset);
+        let index = match find_name(name.clone(), names) {
+            None => { fns.len() }
+            Some(index) => index,
+        };
+        names.push((name, index));
+        new_state = push_fn(&new_state, fns, Op::FnRef(index));
+        loop {
+            if let Ok(range) = end_node(node, data, offset) {
+                update(range, &mut data, &mut offset);
+                break;
+            } else if let Ok(range) = end_node("member_lambda", data, offset) {
+                update(range, &mut data, &mut offset);
+            } else if let Ok((range, state)) = read_arg(
+                    data, offset, fns, &new_state, names, &mut vec![], ignored
                 ) {
                 update(range, &mut data, &mut offset);
-                new_state = push_fn(&state, fns, Op::OpRef(ops.len() - 1));
+                new_state = state;
             } else {
                 let range = ignore(data, offset);
                 update(range, &mut data, &mut offset);
@@ -362,404 +696,198 @@ pub fn convert(
             }
         }
 
+        // The member returns itself.
+        new_state = push_op(&new_state, ops, Op::End);
+        new_state = push_op(&new_state, ops, Op::FnRef(index));
+        new_state = push_fn(&new_state, fns, Op::OpRef(ops.len() - 1));
         new_state = push_fn(&new_state, fns, Op::End);
         Ok((Range::new(start_offset, offset - start_offset), new_state))
     }
 
-    let mut fns = vec![];
-    let mut ops = vec![];
-    let mut names = vec![];
+    // Reads `use a::b;` and ignores it.
+    //
+    // All loaded functions share one namespace and names are resolved where they are used,
+    // so there is nothing to import. Modules are not supported yet.
+    fn read_use(
+        mut data: &[Range<MetaData>],
+        mut offset: usize,
+        ignored: &mut Vec<Range>
+    ) -> Result<Range, ()> {
+        let start_offset = offset;
+        let node = "use";
+        let range = start_node(node, data, offset)?;
+        update(range, &mut data, &mut offset);
+        loop {
+            if let Ok(range) = end_node(node, data, offset) {
+                update(range, &mut data, &mut offset);
+                break;
+            } else {
+                let range = ignore(data, offset);
+                update(range, &mut data, &mut offset);
+                ignored.push(range);
+            }
+        }
+        Ok(Range::new(start_offset, offset - start_offset))
+    }
+
     let mut offset = 0;
-    let mut state = ConvertState(0, 0);
+    let mut state = ConvertState(fns.len(), ops.len());
 
     loop {
-        if let Ok((range, new_state)) = read_fn(data, offset, &mut fns,
-            &mut ops, &state, &mut names, ignored)
+        if let Ok((range, new_state)) = read_fn(data, offset, fns,
+            ops, &state, names, ignored)
         {
             update(range, &mut data, &mut offset);
             state = new_state;
-        } else if offset < data.len() {
+        } else if let Ok((range, new_state)) = read_member(data, offset,
+            fns, ops, &state, names, ignored)
+        {
+            update(range, &mut data, &mut offset);
+            state = new_state;
+        } else if let Ok(range) = read_use(data, offset, ignored) {
+            update(range, &mut data, &mut offset);
+        } else if !data.is_empty() {
             return Err(());
         } else {
             break;
         }
     }
 
-    Ok((fns, ops))
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use super::Op::*;
+    use builder::ProgramBuilder;
+    use program::Program;
 
-    #[test]
-    fn not_true() {
-        let fns = vec![
-            // bool,
-            FnRef(0),                   // 0: bool
-            End,
-            // false,
-            FnRef(2),                   // 2: false
-            End,
-            // true,
-            FnRef(4),                   // 4: true
-            End,
-            // not(bool) -> bool
-            FnRef(6),                   // 6: not
-            FnRef(0),                   // 7: bool,
-            OpRef(1),                   // 8: -> bool
-            // not([true] true) -> [false] false
-            FnRef(6),                   // 9: not
-            Path,
-            FnRef(4),                   // 11: true
-            FnRef(4),                   // 12: true
-            OpRef(5),                   // 13: -> [false] false,
-        ];
+    fn bool_builder() -> ProgramBuilder {
+        ProgramBuilder::new()
+            .member("false", "bool")
+            .member("true", "bool")
+    }
 
-        let ops = vec![
-            // bool
-            End,
-            FnRef(0),                   // 1: bool
-            // [false] false
-            End,
-            Path,
-            FnRef(2),                   // 4: false
-            FnRef(2),                   // 5: false
-            // call not([true] true)
-            End,
-            Call,
-            FnRef(6),                   // 8: not
-            Path,
-            FnRef(4),                   // 10: true
-            FnRef(4),                   // 11: true
-        ];
+    fn not_program() -> Program {
+        bool_builder()
+            .func("not", &["bool"], "bool")
+            .clause(&["[true] true"], "[false] false")
+            .clause(&["[false] false"], "[true] true")
+            .program().unwrap()
+    }
 
+    fn eval(fns: &[Op], ops: &[Op], st: &mut Vec<Op>) {
+        if let Err(err) = try_eval(fns, ops, st) {
+            panic!("{}", err);
+        }
+    }
+
+    // Evaluates instructions after the instructions of a program.
+    fn eval_with(program: &Program, ops: &[Op]) -> Vec<Op> {
+        let mut all_ops = program.ops.clone();
+        all_ops.extend_from_slice(ops);
         let mut stack = vec![];
+        eval(&program.fns, &all_ops, &mut stack);
+        stack
+    }
 
-        eval(&fns, &ops, &mut stack);
+    #[test]
+    fn not_true() {
+        let program = not_program();
+        let f = |name| program.find(name).unwrap();
+        let (not, true_, false_) = (f("not"), f("true"), f("false"));
 
-        assert_eq!(&stack, &[
-            FnRef(2),   // false
-            FnRef(2),
-            Path
+        // call not([true] true)
+        let stack = eval_with(&program, &[
+            End, Call, FnRef(not), Path, FnRef(true_), FnRef(true_)
         ]);
+        assert_eq!(&stack, &[FnRef(false_), FnRef(false_), Path]);
     }
 
     #[test]
     fn not_false() {
-        let fns = vec![
-            // bool,
-            FnRef(0),                   // 0: bool
-            End,
-            // false,
-            FnRef(2),                   // 2: false
-            End,
-            // true,
-            FnRef(4),                   // 4: true
-            End,
-            // not(bool) -> bool
-            FnRef(6),                   // 6: not
-            FnRef(0),                   // 7: bool,
-            OpRef(1),                   // 8: -> bool
-            // not([true] true) -> [false] false
-            FnRef(6),                   // 9: not
-            Path,
-            FnRef(4),                   // 11: true
-            FnRef(4),                   // 12: true
-            OpRef(5),                   // 13: -> [false] false,
-            // not([false] false) -> [true] true
-            FnRef(6),                   // 14: not
-            Path,
-            FnRef(2),                   // 16: false
-            FnRef(2),                   // 17: false
-            OpRef(9),                   // 18: -> [true] true
-        ];
-
-        let ops = vec![
-            // bool
-            End,
-            FnRef(0),                   // 1: bool
-            // [false] false
-            End,
-            Path,
-            FnRef(2),                   // 4: false
-            FnRef(2),                   // 5: false
-            // [true] true
-            End,
-            Path,
-            FnRef(4),                   // 8: false
-            FnRef(4),                   // 9: false
-            // call not([true] true)
-            End,
-            Call,
-            FnRef(6),                   // 8: not
-            Path,
-            FnRef(2),                   // 10: false
-            FnRef(2),                   // 11: false
-        ];
-
-        let mut stack = vec![];
+        let program = not_program();
+        let f = |name| program.find(name).unwrap();
+        let (not, true_, false_) = (f("not"), f("true"), f("false"));
 
-        eval(&fns, &ops, &mut stack);
-
-        assert_eq!(&stack, &[
-            FnRef(4),   // true
-            FnRef(4),
-            Path
+        // call not([false] false)
+        let stack = eval_with(&program, &[
+            End, Call, FnRef(not), Path, FnRef(false_), FnRef(false_)
         ]);
+        assert_eq!(&stack, &[FnRef(true_), FnRef(true_), Path]);
     }
 
     #[test]
     fn not_not_false() {
-        let fns = vec![
-            // bool,
-            FnRef(0),                   // 0: bool
-            End,
-            // false,
-            FnRef(2),                   // 2: false
-            End,
-            // true,
-            FnRef(4),                   // 4: true
-            End,
-            // not(bool) -> bool
-            FnRef(6),                   // 6: not
-            FnRef(0),                   // 7: bool,
-            OpRef(1),                   // 8: -> bool
-            // not([true] true) -> [false] false
-            FnRef(6),                   // 9: not
-            Path,
-            FnRef(4),                   // 11: true
-            FnRef(4),                   // 12: true
-            OpRef(5),                   // 13: -> [false] false,
-            // not([false] false) -> [true] true
-            FnRef(6),                   // 14: not
-            Path,
-            FnRef(2),                   // 16: false
-            FnRef(2),                   // 17: false
-            OpRef(9),                   // 18: -> [true] true
-        ];
+        let program = not_program();
+        let f = |name| program.find(name).unwrap();
+        let (not, false_) = (f("not"), f("false"));
 
-        let ops = vec![
-            // bool
-            End,
-            FnRef(0),                   // 1: bool
-            // [false] false
-            End,
-            Path,
-            FnRef(2),                   // 4: false
-            FnRef(2),                   // 5: false
-            // [true] true
-            End,
-            Path,
-            FnRef(4),                   // 8: false
-            FnRef(4),                   // 9: false
-            // call not([true] true)
-            End,
-            Call,
-            FnRef(6),                   // 8: not
-            Call,
-            FnRef(6),                   // 10: not
-            Path,
-            FnRef(2),                   // 12: false
-            FnRef(2),                   // 13: false
-        ];
-
-        let mut stack = vec![];
-
-        eval(&fns, &ops, &mut stack);
-
-        assert_eq!(&stack, &[
-            FnRef(2),   // false
-            FnRef(2),
-            Path
+        // call not(not([false] false))
+        let stack = eval_with(&program, &[
+            End, Call, FnRef(not), Call, FnRef(not), Path, FnRef(false_), FnRef(false_)
         ]);
+        assert_eq!(&stack, &[FnRef(false_), FnRef(false_), Path]);
     }
 
     #[test]
     fn not_type() {
-        let fns = vec![
-            // bool,
-            FnRef(0),                   // 0: bool
-            End,
-            // false,
-            FnRef(2),                   // 2: false
-            End,
-            // true,
-            FnRef(4),                   // 4: true
-            End,
-            // not(bool) -> bool
-            FnRef(6),                   // 6: not
-            FnRef(0),                   // 7: bool,
-            OpRef(1),                   // 8: -> bool
-            // not([true] true) -> [false] false
-            FnRef(6),                   // 9: not
-            Path,
-            FnRef(4),                   // 11: true
-            FnRef(4),                   // 12: true
-            OpRef(5),                   // 13: -> [false] false,
-        ];
+        let program = not_program();
+        let f = |name| program.find(name).unwrap();
+        let (not, bool_) = (f("not"), f("bool"));
 
-        let ops = vec![
-            // bool
-            End,
-            FnRef(0),                   // 1: bool
-            // [false] false
-            End,
-            Path,
-            FnRef(2),                   // 4: false
-            FnRef(2),                   // 5: false
-            // call not(bool)
-            End,
-            Call,
-            FnRef(6),                   // 8: not
-            FnRef(0),
-        ];
-
-        let mut stack = vec![];
-
-        eval(&fns, &ops, &mut stack);
-
-        assert_eq!(&stack, &[
-            FnRef(0),   // bool
+        // call not(bool)
+        let stack = eval_with(&program, &[
+            End, Call, FnRef(not), FnRef(bool_)
         ]);
+        assert_eq!(&stack, &[FnRef(bool_)]);
     }
 
     #[test]
     fn identity() {
-        let fns = vec![
-            // bool,
-            FnRef(0),                   // 0: bool
-            End,
-            // false,
-            FnRef(2),                   // 2: false
-            End,
-            // true,
-            FnRef(4),                   // 4: true
-            End,
-            // id(X) -> X
-            FnRef(6),                   // 6: id
-            Var(0),                     // 7: X,
-            OpRef(1),                   // 8: -> X
-        ];
-
-        let ops = vec![
-            // X
-            End,
-            Var(0),                     // 1: X
-            End,
-            Call,
-            FnRef(6),                   // 8: id
-            Path,
-            FnRef(2),                   // 10: [false]
-            FnRef(2),                   // 11: false
-        ];
-
-        let mut stack = vec![];
-
-        eval(&fns, &ops, &mut stack);
-
-        assert_eq!(&stack, &[
-            FnRef(2),   // false
-            FnRef(2),   // [false]
-            Path,
+        let program = bool_builder()
+            .func("id", &["X"], "X")
+            .program().unwrap();
+        let f = |name| program.find(name).unwrap();
+        let (id, false_) = (f("id"), f("false"));
+
+        // call id([false] false)
+        let stack = eval_with(&program, &[
+            End, Call, FnRef(id), Path, FnRef(false_), FnRef(false_)
         ]);
+        assert_eq!(&stack, &[FnRef(false_), FnRef(false_), Path]);
     }
 
     #[test]
     fn equal() {
-        let fns = vec![
-            // bool,
-            FnRef(0),                   // 0: bool
-            End,
-            // false,
-            FnRef(2),                   // 2: false
-            End,
-            // true,
-            FnRef(4),                   // 4: true
-            End,
-            // eq(X, X) -> [true] true
-            FnRef(6),                   // 6: eq
-            Var(0),                     // 7: X,
-            Var(0),                     // 8: X,
-            OpRef(3),                   // 9: -> [true] true
-        ];
-
-        let ops = vec![
-            // [true] true
-            End,
-            Path,
-            FnRef(4),                   // 2: [true]
-            FnRef(4),                   // 1: true
-            End,
-            Call,
-            FnRef(6),                   // 8: eq
-            FnRef(0),                   // 9: bool
-            FnRef(0),                   // 10: bool
-        ];
-
-        let mut stack = vec![];
-
-        eval(&fns, &ops, &mut stack);
-
-        assert_eq!(&stack, &[
-            FnRef(4),   // true
-            FnRef(4),   // [true]
-            Path,
+        let program = bool_builder()
+            .func("eq", &["X", "X"], "[true] true")
+            .program().unwrap();
+        let f = |name| program.find(name).unwrap();
+        let (eq, bool_, true_) = (f("eq"), f("bool"), f("true"));
+
+        // call eq(bool, bool)
+        let stack = eval_with(&program, &[
+            End, Call, FnRef(eq), FnRef(bool_), FnRef(bool_)
         ]);
+        assert_eq!(&stack, &[FnRef(true_), FnRef(true_), Path]);
     }
 
     #[test]
     fn not_equal() {
-        let fns = vec![
-            // bool,
-            FnRef(0),                   // 0: bool
-            End,
-            // false,
-            FnRef(2),                   // 2: false
-            End,
-            // true,
-            FnRef(4),                   // 4: true
-            End,
-            // eq(X, X) -> [true] true
-            FnRef(6),                   // 6: eq
-            Var(0),                     // 7: X,
-            Var(0),                     // 8: X,
-            OpRef(3),                   // 9: -> [true] true
-            // eq(X, Y) -> [false] false
-            FnRef(6),                   // 6: eq
-            Var(0),                     // 7: X,
-            Var(1),                     // 8: Y,
-            OpRef(7),                   // 9: -> [false] false
-        ];
-
-        let ops = vec![
-            // [true] true
-            End,
-            Path,
-            FnRef(4),                   // 2: [true]
-            FnRef(4),                   // 3: true
-            // [false] false
-            End,
-            Path,
-            FnRef(2),                   // 6: [false]
-            FnRef(2),                   // 7: false
-            End,
-            Call,
-            FnRef(6),                   // 8: eq
-            FnRef(4),                   // 9: true
-            FnRef(2),                   // 10: false
-        ];
-
-        let mut stack = vec![];
-
-        eval(&fns, &ops, &mut stack);
-
-        assert_eq!(&stack, &[
-            FnRef(2),   // false
-            FnRef(2),   // [false]
-            Path,
+        let program = bool_builder()
+            .func("eq", &["X", "X"], "[true] true")
+            .clause(&["X", "Y"], "[false] false")
+            .program().unwrap();
+        let f = |name| program.find(name).unwrap();
+        let (eq, true_, false_) = (f("eq"), f("true"), f("false"));
+
+        // call eq(true, false)
+        let stack = eval_with(&program, &[
+            End, Call, FnRef(eq), FnRef(true_), FnRef(false_)
         ]);
+        assert_eq!(&stack, &[FnRef(false_), FnRef(false_), Path]);
     }
 
     #[test]
@@ -773,7 +901,7 @@ fn true(bool) -> true;
 fn false(bool) -> false;
         ";
         let mut data = vec![];
-        parse(&rules, &source, &mut data).unwrap();
+        parse(&rules, source, &mut data).unwrap();
         let (fns, ops) = convert(&data, &mut vec![]).unwrap();
         assert_eq!(&fns, &[
             // bool() -> bool
@@ -814,7 +942,7 @@ fn or(bool, bool) -> bool;
 fn or([false] false, [false] false) -> [false] false;
         ";
         let mut data = vec![];
-        parse(&rules, &source, &mut data).unwrap();
+        parse(&rules, source, &mut data).unwrap();
         // json::print(&data);
         let (fns, ops) = convert(&data, &mut vec![]).unwrap();
         assert_eq!(&fns, &vec![
@@ -863,9 +991,225 @@ fn or([false] false, [false] false) -> [false] false;
             FnRef(0),
             // -> [false] false
             End,
+            Path,
             FnRef(7),
-            FnRef(7),
-            Path
+            FnRef(7)
+        ]);
+    }
+
+    #[test]
+    fn members_cases_and_variables() {
+        use piston_meta::parse;
+
+        let rules = ::syntax_rules();
+        let source = "
+fn type() -> type;
+bool: type;
+true: bool;
+false: bool;
+fn not(bool) -> bool;
+[:] (true) -> false;
+[:] (false) -> true;
+fn first(X, _) -> X;
+        ";
+        let mut data = vec![];
+        parse(&rules, source, &mut data).unwrap();
+        let (fns, mut ops) = convert(&data, &mut vec![]).unwrap();
+        assert_eq!(&fns[3..7], &[
+            // bool: type
+            FnRef(3),           // bool
+            FnRef(0),           // type
+            OpRef(3),           // -> bool
+            End
+        ]);
+        assert_eq!(&fns[19..], &[
+            // [:] (true) -> false
+            FnRef(15),          // not
+            FnRef(7),           // true
+            OpRef(11),          // -> false
+            End,
+            // [:] (false) -> true
+            FnRef(15),          // not
+            FnRef(11),          // false
+            OpRef(13),          // -> true
+            End,
+            // first(X, _) -> X
+            FnRef(27),          // first
+            Var(0),             // X
+            Var(1),             // _
+            OpRef(15),          // -> X
+            End
+        ]);
+
+        // call not(false)
+        ops.extend_from_slice(&[End, Call, FnRef(15), FnRef(11)]);
+        let mut stack = vec![];
+        eval(&fns, &ops, &mut stack);
+        assert_eq!(&stack, &[FnRef(7)]);    // true
+
+        // call first(false, true)
+        ops.extend_from_slice(&[End, Call, FnRef(27), FnRef(11), FnRef(7)]);
+        let mut stack = vec![];
+        eval(&fns, &ops, &mut stack);
+        assert_eq!(&stack, &[FnRef(11)]);   // false
+    }
+
+    #[test]
+    fn nested_return() {
+        use piston_meta::parse;
+
+        let rules = ::syntax_rules();
+        let source = "
+fn type() -> type;
+bool: type;
+true: bool;
+false: bool;
+fn id(X) -> X;
+fn first(X, _) -> X;
+fn f(X, Y) -> first(X, id(Y));
+        ";
+        let mut data = vec![];
+        parse(&rules, source, &mut data).unwrap();
+        let (fns, mut ops) = convert(&data, &mut vec![]).unwrap();
+        assert_eq!(&ops[ops.len() - 7..], &[
+            // -> first(X, id(Y))
+            End,
+            Call,
+            FnRef(19),          // first
+            Var(0),             // X
+            Call,
+            FnRef(15),          // id
+            Var(1)              // Y
+        ]);
+
+        // call f(true, false)
+        ops.extend_from_slice(&[End, Call, FnRef(24), FnRef(7), FnRef(11)]);
+        let mut stack = vec![];
+        eval(&fns, &ops, &mut stack);
+        assert_eq!(&stack, &[FnRef(7)]);    // true
+    }
+
+    #[test]
+    fn short_stack() {
+        use piston_meta::parse;
+
+        let rules = ::syntax_rules();
+        let source = "
+fn type() -> type;
+bool: type;
+true: bool;
+false: bool;
+fn f(X, Y) -> Y;
+[:] (X) -> X;
+        ";
+        let mut data = vec![];
+        parse(&rules, source, &mut data).unwrap();
+        let (fns, mut ops) = convert(&data, &mut vec![]).unwrap();
+
+        // call f(false), which is shorter than the first case
+        ops.extend_from_slice(&[End, Call, FnRef(15), FnRef(11)]);
+        let mut stack = vec![];
+        eval(&fns, &ops, &mut stack);
+        assert_eq!(&stack, &[FnRef(11)]);   // false
+    }
+
+    #[test]
+    fn path_error() {
+        let mut program = bool_builder()
+            .func("p", &["[true] X"], "X")
+            .func("f", &["[p] X"], "X")
+            .program().unwrap();
+        let f = program.find("f").unwrap();
+        let false_ = program.find("false").unwrap();
+        // Break the path of `p`, such that calling `p` fails.
+        let p = program.find("p").unwrap();
+        program.fns[p + 2] = Var(1);
+
+        // call f(false)
+        program.ops.extend_from_slice(&[End, Call, FnRef(f), FnRef(false_)]);
+        let mut stack = vec![];
+        assert_eq!(try_eval(&program.fns, &program.ops, &mut stack).unwrap_err(),
+                   "Expected `FnRef` after `Path`, found `Some(Var(1))`");
+    }
+
+    #[test]
+    fn path_pattern() {
+        use piston_meta::parse;
+
+        let rules = ::syntax_rules();
+        let source = "
+fn type() -> type;
+bool: type;
+true: bool;
+false: bool;
+fn not(bool) -> bool;
+[:] (true) -> false;
+[:] (false) -> true;
+fn is_false([not] X) -> X;
+        ";
+        let mut data = vec![];
+        parse(&rules, source, &mut data).unwrap();
+        let (fns, mut ops) = convert(&data, &mut vec![]).unwrap();
+        assert_eq!(&fns[27..], &[
+            // is_false([not] X) -> X
+            FnRef(27),          // is_false
+            Path,
+            FnRef(15),          // [not]
+            Var(0),             // X
+            OpRef(15),          // -> X
+            End
+        ]);
+
+        // call is_false(false)
+        ops.extend_from_slice(&[End, Call, FnRef(27), FnRef(11)]);
+        let mut stack = vec![];
+        eval(&fns, &ops, &mut stack);
+        assert_eq!(&stack, &[FnRef(7)]);    // true
+
+        // call is_false(true)
+        ops.extend_from_slice(&[End, Call, FnRef(27), FnRef(7)]);
+        let mut stack = vec![];
+        eval(&fns, &ops, &mut stack);
+        assert_eq!(&stack, &[FnRef(11)]);   // false
+    }
+
+    #[test]
+    fn siblings() {
+        use piston_meta::parse;
+
+        let rules = ::syntax_rules();
+        let source = format!("
+fn type() -> type;
+bool: type;
+true: bool;
+false: bool;
+{}", include_str!("../assets/the-simpsons.txt"));
+        let mut data = vec![];
+        parse(&rules, &source, &mut data).unwrap();
+        let (fns, mut ops) = convert(&data, &mut vec![]).unwrap();
+
+        // call siblings(bart, lisa)
+        ops.extend_from_slice(&[
+            End,
+            Call,
+            FnRef(71),          // siblings
+            FnRef(27),          // bart
+            FnRef(31),          // lisa
+        ]);
+        let mut stack = vec![];
+        eval(&fns, &ops, &mut stack);
+        assert_eq!(&stack, &[FnRef(7)]);    // true
+
+        // call siblings(maggie, character)
+        ops.extend_from_slice(&[
+            End,
+            Call,
+            FnRef(71),          // siblings
+            FnRef(35),          // maggie
+            FnRef(15),          // character
         ]);
+        let mut stack = vec![];
+        eval(&fns, &ops, &mut stack);
+        assert_eq!(&stack, &[FnRef(11)]);   // false
     }
 }
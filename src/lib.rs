@@ -6,8 +6,13 @@ extern crate range;
 extern crate piston_meta;
 
 use piston_meta::Syntax;
+use std::sync::OnceLock;
 
+pub mod builder;
 pub mod interpreter;
+pub mod program;
+pub mod sym_paths;
+pub mod term;
 
 /// Gets the syntax rules.
 pub fn syntax_rules() -> Syntax {
@@ -20,6 +25,36 @@ pub fn syntax_rules() -> Syntax {
     bootstrap::convert(&res, &mut vec![]).unwrap()
 }
 
+/// Gets the syntax rules for an expression, e.g. `not(not(false))`.
+pub fn expression_rules() -> Syntax {
+    use piston_meta::*;
+
+    let meta_rules = bootstrap::rules();
+    let source = format!("{}\n{}", include_str!("../assets/syntax.txt"),
+                         "16 expression = [?w arg:\"arg\" ?w]");
+    let mut res = vec![];
+    parse(&meta_rules, &source, &mut res).unwrap();
+    bootstrap::convert(&res, &mut vec![]).unwrap()
+}
+
+// Gets the syntax rules, which are built once.
+fn shared_syntax_rules() -> &'static Syntax {
+    static RULES: OnceLock<Syntax> = OnceLock::new();
+    RULES.get_or_init(syntax_rules)
+}
+
+// Gets the syntax rules for an expression, which are built once.
+fn shared_expression_rules() -> &'static Syntax {
+    static RULES: OnceLock<Syntax> = OnceLock::new();
+    RULES.get_or_init(expression_rules)
+}
+
+// Returns `true` if the name refers to a variable,
+// which starts with an upper case letter or is `_`.
+fn is_var(name: &str) -> bool {
+    name == "_" || name.chars().next().map(|c| c.is_uppercase()).unwrap_or(false)
+}
+
 #[cfg(test)]
 mod tests {
     use piston_meta::*;
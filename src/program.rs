@@ -0,0 +1,365 @@
+//! Loaded programs.
+
+use piston_meta::*;
+use std::error;
+use std::fmt;
+use std::sync::Arc;
+
+use interpreter::{self, Op};
+use term::Term;
+
+/// An error from querying a program.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// Could not parse the expression.
+    Parse(String),
+    /// The expression refers to a name that is not declared.
+    Lower(String),
+    /// No function case matched during evaluation.
+    Eval(String),
+    /// The result could not be decoded as a value.
+    Decode(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Parse(ref err) => write!(f, "Parse error: {}", err),
+            Error::Lower(ref err) => write!(f, "Lower error: {}", err),
+            Error::Eval(ref err) => write!(f, "Eval error: {}", err),
+            Error::Decode(ref err) => write!(f, "Decode error: {}", err),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+/// Stores a loaded program.
+///
+/// A value is stored as stack instructions, e.g. `[false] false`
+/// is `[FnRef(false), FnRef(false), Path]`.
+#[derive(Clone, Debug, Default)]
+pub struct Program {
+    /// Function cases.
+    pub fns: Vec<Op>,
+    /// Instructions.
+    pub ops: Vec<Op>,
+    /// Names of declared functions with function index.
+    pub names: Vec<(Arc<String>, usize)>,
+}
+
+impl Program {
+    /// Creates a new empty program.
+    pub fn new() -> Program {
+        Program::default()
+    }
+
+    /// Creates a new program from source.
+    pub fn from_source(source: &str) -> Result<Program, String> {
+        let mut program = Program::new();
+        program.load(source)?;
+        Ok(program)
+    }
+
+    /// Loads declarations from source.
+    ///
+    /// The declarations can use functions that are already loaded.
+    /// All functions share one namespace, so `use` declarations are ignored.
+    pub fn load(&mut self, source: &str) -> Result<(), String> {
+        let rules = ::shared_syntax_rules();
+        let mut data = vec![];
+        if let Err(err) = parse(rules, source, &mut data) {
+            let mut w: Vec<u8> = vec![];
+            ParseErrorHandler::new(source).write(&mut w, err)
+                .map_err(|err| format!("{}", err))?;
+            return Err(String::from_utf8_lossy(&w).into_owned());
+        }
+        let mut fns = self.fns.clone();
+        let mut ops = self.ops.clone();
+        let mut names = self.names.clone();
+        interpreter::convert_extend(&data, &mut fns, &mut ops, &mut names, &mut vec![])
+            .map_err(|_| "Could not convert meta data".to_string())?;
+        self.fns = fns;
+        self.ops = ops;
+        self.names = names;
+        Ok(())
+    }
+
+    /// Finds the function index of a name.
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.names.iter().find(|e| &**e.0 == name).map(|e| e.1)
+    }
+
+    /// Gets the name of a function.
+    pub fn name(&self, f: usize) -> Option<&str> {
+        self.names.iter().find(|e| e.1 == f).map(|e| &**e.0)
+    }
+
+    /// Returns the start and `OpRef` position of every function case.
+    pub fn cases(&self) -> Vec<(usize, usize)> {
+        let mut res = vec![];
+        let mut start = 0;
+        for (i, op) in self.fns.iter().enumerate() {
+            match *op {
+                Op::OpRef(_) => {
+                    res.push((start, i));
+                    start = i + 1;
+                }
+                Op::End => { start = i + 1; }
+                _ => {}
+            }
+        }
+        res
+    }
+
+    /// Returns the members of a type.
+    ///
+    /// A member is declared as `a: b;`, which is the same as `fn a(b) -> a;`.
+    pub fn members(&self, ty: usize) -> Vec<usize> {
+        let mut res = vec![];
+        for (start, pos) in self.cases() {
+            if let (&Op::FnRef(x), &Op::FnRef(y), &Op::OpRef(o)) =
+                (&self.fns[start], &self.fns[start + 1], &self.fns[pos])
+            {
+                if pos == start + 2 && y == ty &&
+                   self.ops[o] == Op::FnRef(x) && self.ops[o - 1] == Op::End &&
+                   !res.contains(&x)
+                {
+                    res.push(x);
+                }
+            }
+        }
+        res
+    }
+
+    /// Returns the argument types and return type of a function.
+    ///
+    /// This is the first case of the function, e.g. `fn not(bool) -> bool;`.
+    /// Returns `None` if the types are not plain names.
+    pub fn signature(&self, f: usize) -> Option<(Vec<usize>, usize)> {
+        if self.fns.get(f) != Some(&Op::FnRef(f)) {return None};
+        let (_, pos) = self.cases().into_iter().find(|c| c.0 == f)?;
+        let mut args = vec![];
+        for op in &self.fns[f + 1..pos] {
+            if let Op::FnRef(ty) = *op {
+                args.push(ty);
+            } else {
+                return None;
+            }
+        }
+        if let Op::OpRef(o) = self.fns[pos] {
+            if let (Op::End, Op::FnRef(ty)) = (self.ops[o - 1], self.ops[o]) {
+                return Some((args, ty));
+            }
+        }
+        None
+    }
+
+    /// Calls a function with argument values.
+    ///
+    /// Returns the resulting value.
+    pub fn call(&self, f: usize, args: &[Term]) -> Result<Term, String> {
+        let mut st = vec![];
+        for arg in args.iter().rev() {
+            st.extend(arg.to_stack(self)?);
+        }
+        st.push(Op::FnRef(f));
+        interpreter::call(&self.fns, &self.ops, &mut st)?;
+        Term::from_stack(self, &st)
+    }
+
+    /// Evaluates an expression, e.g. `not(not(false))`.
+    pub fn eval_str(&self, source: &str) -> Result<Term, Error> {
+        let term = Term::parse(source).map_err(Error::Parse)?;
+        let mut ops = self.ops.clone();
+        ops.push(Op::End);
+        ops.extend(term.to_ops(self).map_err(Error::Lower)?);
+        let mut st = vec![];
+        interpreter::try_eval(&self.fns, &ops, &mut st).map_err(Error::Eval)?;
+        Term::from_stack(self, &st).map_err(Error::Decode)
+    }
+
+    /// Adds a function case.
+    ///
+    /// The arguments and return value are instructions in the order
+    /// they are written, e.g. `[false] false` is `[Path, FnRef(false), FnRef(false)]`.
+    pub fn add_case(&mut self, f: usize, args: &[Op], ret: &[Op]) {
+        self.ops.push(Op::End);
+        self.ops.extend_from_slice(ret);
+        self.fns.push(Op::FnRef(f));
+        self.fns.extend_from_slice(args);
+        self.fns.push(Op::OpRef(self.ops.len() - 1));
+        self.fns.push(Op::End);
+    }
+
+    /// Returns the functions with a type signature.
+    pub fn functions(&self) -> Vec<usize> {
+        let mut res = vec![];
+        for &(_, f) in &self.names {
+            if !res.contains(&f) && self.signature(f).is_some() {
+                res.push(f);
+            }
+        }
+        res
+    }
+
+    /// Returns every combination of members of the argument types of `f`.
+    pub fn arguments(&self, f: usize) -> Result<Vec<Vec<Term>>, String> {
+        let (args, _) = match self.signature(f) {
+            None => { return Err("Expected function with type signature".into()); }
+            Some(x) => x,
+        };
+        let domains: Vec<Vec<Term>> = args.iter().map(|&ty| {
+            self.members(ty).into_iter().map(|x| self.name(x).map(Term::sym)
+                .ok_or_else(|| format!("Could not find name of function `{}`", x)))
+                .collect::<Result<Vec<Term>, String>>()
+        }).collect::<Result<_, _>>()?;
+        let mut res = vec![];
+        if domains.iter().any(|d| d.is_empty()) {return Ok(res)};
+        let mut ind = vec![0; domains.len()];
+        'ind: loop {
+            res.push(ind.iter().enumerate()
+                .map(|(k, &i)| domains[k][i].clone()).collect());
+            // Go to next combination of arguments.
+            for k in (0..ind.len()).rev() {
+                ind[k] += 1;
+                if ind[k] < domains[k].len() {continue 'ind};
+                ind[k] = 0;
+            }
+            break;
+        }
+        Ok(res)
+    }
+
+    /// Computes the existential path `∃f`, the set of values returned by `f`.
+    ///
+    /// Every combination of members of the argument types is evaluated.
+    /// Arguments where `f` is not defined are skipped.
+    pub fn existential_path(&self, f: usize) -> Result<Vec<Term>, String> {
+        let mut res = vec![];
+        for args in self.arguments(f)? {
+            if let Ok(val) = self.call(f, &args) {
+                if !res.contains(&val) {res.push(val)};
+            }
+        }
+        Ok(res)
+    }
+
+    /// Adds the existential path `∃f` as a function `∃f(R) -> bool`.
+    ///
+    /// The function returns `true` for values returned by `f`.
+    /// Requires `bool`, `true` and `false` to be declared.
+    /// Returns the function index.
+    pub fn add_existential_path(&mut self, f: usize) -> Result<usize, String> {
+        let name = match self.name(f) {
+            None => { return Err("Expected function with name".into()); }
+            Some(name) => format!("∃{}", name),
+        };
+        if let Some(index) = self.find(&name) {return Ok(index)};
+        let find = |name: &str| self.find(name)
+            .ok_or_else(|| format!("Could not find `{}`", name));
+        let (bool_, true_, false_) = (find("bool")?, find("true")?, find("false")?);
+        let ret = match self.signature(f) {
+            None => { return Err("Expected function with type signature".into()); }
+            Some((_, ret)) => ret,
+        };
+        let values = self.existential_path(f)?.iter()
+            .map(|val| val.to_ops(self)).collect::<Result<Vec<_>, _>>()?;
+
+        let index = self.fns.len();
+        self.names.push((Arc::new(name), index));
+        self.add_case(index, &[Op::FnRef(ret)], &[Op::FnRef(bool_)]);
+        for val in &values {
+            self.add_case(index, val, &[Op::FnRef(true_)]);
+        }
+        self.add_case(index, &[Op::Var(0)], &[Op::FnRef(false_)]);
+        Ok(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn simpsons() -> Program {
+        Program::from_source(&format!("
+fn type() -> type;
+bool: type;
+true: bool;
+false: bool;
+{}", include_str!("../assets/the-simpsons.txt"))).unwrap()
+    }
+
+    #[test]
+    fn eval_str() {
+        let program = Program::from_source("
+fn bool() -> bool;
+true: bool;
+false: bool;
+fn not(bool) -> bool;
+[:] (true) -> false;
+[:] (false) -> true;
+fn or(bool, bool) -> bool;
+[:] (false, false) -> false;
+[:] (_, _) -> true;
+fn nor(bool, bool) -> bool;
+[:] (X, Y) -> not(or(X, Y));
+fn or_not(bool, bool) -> bool;
+[:] (X, Y) -> or(X, nor(Y, Y));
+fn wrap(bool) -> bool;
+[:] (X) -> [not] X;
+        ").unwrap();
+        assert_eq!(program.eval_str("not(not(false))"), Ok(Term::sym("false")));
+        assert_eq!(program.eval_str("nor(false, not(true))"), Ok(Term::sym("true")));
+        assert_eq!(program.eval_str("nor(not(false), false)"), Ok(Term::sym("false")));
+        assert_eq!(program.eval_str("or_not(false, true)"), Ok(Term::sym("false")));
+        assert_eq!(program.eval_str("wrap(true)"),
+                   Ok(Term::Path(Arc::new("not".into()), Box::new(Term::sym("true")))));
+        assert_eq!(program.eval_str("[not] true"), program.eval_str("wrap(true)"));
+        assert!(matches!(program.eval_str("not("), Err(Error::Parse(_))));
+        assert!(matches!(program.eval_str("not(maybe)"), Err(Error::Lower(_))));
+        assert!(matches!(program.eval_str("not(not)"), Err(Error::Eval(_))));
+    }
+
+    #[test]
+    fn members() {
+        let program = simpsons();
+        let character = program.find("character").unwrap();
+        let members: Vec<&str> = program.members(character).into_iter()
+            .map(|x| program.name(x).unwrap()).collect();
+        assert_eq!(members, vec!["homer", "marge", "bart", "lisa", "maggie"]);
+    }
+
+    #[test]
+    fn existential_path() {
+        let mut program = simpsons();
+        let father = program.find("father").unwrap();
+        let true_ = Term::sym("true");
+        let false_ = Term::sym("false");
+        assert_eq!(program.existential_path(father).unwrap(), vec![Term::sym("homer")]);
+
+        let ex_father = program.add_existential_path(father).unwrap();
+        assert_eq!(program.find("∃father"), Some(ex_father));
+        assert_eq!(program.call(ex_father, &[Term::sym("homer")]).unwrap(), true_);
+        assert_eq!(program.call(ex_father, &[Term::sym("bart")]).unwrap(), false_);
+
+        // Use the existential path in a declaration.
+        program.load("
+fn is_father(character) -> bool;
+[:] ([∃father] X) -> X;
+        ").unwrap();
+        let is_father = program.find("is_father").unwrap();
+        assert_eq!(program.call(is_father, &[Term::sym("homer")]).unwrap(), true_);
+        assert_eq!(program.call(is_father, &[Term::sym("bart")]).unwrap(), false_);
+    }
+
+    #[test]
+    fn use_ignored() {
+        let mut program = Program::from_source(include_str!("../assets/bool.txt")).unwrap();
+        let not = program.find("not").unwrap();
+        assert_eq!(program.call(not, &[Term::sym("true")]), Ok(Term::sym("false")));
+        assert!(program.load("use bool::not;").is_ok());
+        assert!(program.load("use nat::*;").is_ok());
+        assert!(program.find("nat").is_none());
+    }
+}
@@ -0,0 +1,431 @@
+//! Discovery of symmetric paths.
+//!
+//! A symmetric path `f[p]` is a function `g` such that
+//! `p(f(x0, x1, ...)) == g(p(x0), p(x1), ...)` for all arguments.
+//! An asymmetric path `f[p0 x p1 x ... -> q]` uses different paths
+//! for arguments and return value.
+//! The paths are found by testing all members of the argument types.
+
+use interpreter::Op;
+use program::Program;
+use term::Term;
+
+/// Stores a symmetric path `f[p] <=> g`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SymPath {
+    /// The function.
+    pub f: usize,
+    /// The path.
+    pub p: usize,
+    /// The function that the path leads to.
+    pub g: usize,
+}
+
+/// Stores an asymmetric path `f[p0 x p1 x ... -> q] <=> g`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct AsymPath {
+    /// The function.
+    pub f: usize,
+    /// The paths of arguments.
+    pub ps: Vec<usize>,
+    /// The path of return value.
+    pub q: usize,
+    /// The function that the path leads to.
+    pub g: usize,
+}
+
+/// Returns `true` if `p(f(x0, x1, ...)) == g(p(x0), p(x1), ...)` for all arguments.
+///
+/// Arguments where `f` or `p` is not defined are skipped.
+/// Returns `false` if no arguments could be tested.
+pub fn check(program: &Program, f: usize, p: usize, g: usize) -> Result<bool, String> {
+    let n = match program.signature(f) {
+        None => { return Err("Expected function with type signature".into()); }
+        Some((args, _)) => args.len(),
+    };
+    check_asym(program, f, &vec![p; n], p, g)
+}
+
+/// Returns `true` if `q(f(x0, x1, ...)) == g(p0(x0), p1(x1), ...)` for all arguments.
+///
+/// Arguments where `f` or a path is not defined are skipped.
+/// Returns `false` if no arguments could be tested.
+pub fn check_asym(
+    program: &Program,
+    f: usize,
+    ps: &[usize],
+    q: usize,
+    g: usize
+) -> Result<bool, String> {
+    let mut tested = false;
+    for args in program.arguments(f)? {
+        if args.len() != ps.len() {
+            return Err("Expected one path per argument".into());
+        }
+        let ls = match program.call(f, &args)
+            .and_then(|val| program.call(q, &[val])) {
+            Ok(val) => val,
+            Err(_) => continue,
+        };
+        let p_args: Result<Vec<Term>, String> = args.into_iter().zip(ps)
+            .map(|(arg, &p)| program.call(p, &[arg])).collect();
+        let p_args = match p_args {
+            Ok(p_args) => p_args,
+            Err(_) => continue,
+        };
+        match program.call(g, &p_args) {
+            Ok(ref rs) if rs == &ls => {}
+            _ => return Ok(false),
+        }
+        tested = true;
+    }
+    Ok(tested)
+}
+
+/// Returns the paths declared in a program, e.g. `fn and [not] ...`.
+pub fn declared(program: &Program) -> Vec<AsymPath> {
+    let fns = &program.fns;
+    let mut res = vec![];
+    for i in 1..fns.len() {
+        if let (Op::FnRef(g), Op::FnPath) = (fns[i - 1], fns[i]) {
+            let refs: Vec<usize> = fns[i + 1..].iter()
+                .take_while(|&op| op != &Op::End)
+                .filter_map(|op| if let Op::FnRef(x) = *op {Some(x)} else {None})
+                .collect();
+            if refs.len() < 2 {continue};
+            res.push(AsymPath {
+                f: refs[0],
+                ps: refs[1..refs.len() - 1].to_vec(),
+                q: refs[refs.len() - 1],
+                g
+            });
+        }
+    }
+    res
+}
+
+/// Checks every path declared in a program.
+pub fn check_declared(program: &Program) -> Result<Vec<(AsymPath, bool)>, String> {
+    let mut res = vec![];
+    for path in declared(program) {
+        let ok = check_asym(program, path.f, &path.ps, path.q, path.g)?;
+        res.push((path, ok));
+    }
+    Ok(res)
+}
+
+/// Finds symmetric paths of `f` by candidate paths.
+///
+/// Every function with the same number of arguments as `f`,
+/// taking the return type of `p` for all arguments, is tried as `g`.
+pub fn find(program: &Program, f: usize, ps: &[usize]) -> Result<Vec<SymPath>, String> {
+    let n = match program.signature(f) {
+        None => { return Err("Expected function with type signature".into()); }
+        Some((args, _)) => args.len(),
+    };
+    let fns = program.functions();
+    let mut res = vec![];
+    for &p in ps {
+        let ty = match program.signature(p) {
+            None => { return Err("Expected path with type signature".into()); }
+            Some((_, ty)) => ty,
+        };
+        let gs = fns.iter().cloned().filter(|&g| match program.signature(g) {
+            None => false,
+            Some((args, _)) => args.len() == n && args.iter().all(|&arg| arg == ty),
+        });
+        for g in gs {
+            if check(program, f, p, g)? {
+                res.push(SymPath {f, p, g});
+            }
+        }
+    }
+    Ok(res)
+}
+
+/// Generates source for a symmetric path, e.g.:
+///
+/// ```text
+/// fn and [not] (bool, bool) -> bool;
+/// [:] (X, Y) -> or(X, Y);
+/// ```
+pub fn to_source(program: &Program, sym: &SymPath) -> Result<String, String> {
+    let name = |f: usize| program.name(f)
+        .ok_or_else(|| format!("Could not find name of function `{}`", f));
+    let n = match program.signature(sym.f) {
+        None => { return Err("Expected function with type signature".into()); }
+        Some((args, _)) => args.len(),
+    };
+    let ty = match program.signature(sym.p) {
+        None => { return Err("Expected path with type signature".into()); }
+        Some((_, ty)) => name(ty)?,
+    };
+    let vars: Vec<String> = (0..n).map(var_name).collect();
+    Ok(format!("fn {} [{}] ({}) -> {};\n[:] ({}) -> {}({});\n",
+        name(sym.f)?, name(sym.p)?, vec![ty; n].join(", "), ty,
+        vars.join(", "), name(sym.g)?, vars.join(", ")))
+}
+
+/// Stores a truth table of a symmetric path `f[p]`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Table {
+    /// The function.
+    pub f: usize,
+    /// The path.
+    pub p: usize,
+    /// Maps `p(x0), p(x1), ...` to `p(f(x0, x1, ...))`.
+    pub cases: Vec<(Vec<Term>, Term)>,
+}
+
+/// Stores two arguments that prove that a symmetric path does not exist.
+///
+/// The path of the arguments are equal, but the path of the results are not.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Conflict {
+    /// The first arguments.
+    pub a: Vec<Term>,
+    /// The second arguments.
+    pub b: Vec<Term>,
+}
+
+/// The result of synthesizing a symmetric path.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Synthesis {
+    /// The symmetric path exists.
+    Table(Table),
+    /// The symmetric path does not exist.
+    Conflict(Conflict),
+}
+
+/// Synthesizes the symmetric path `f[p]` as a truth table.
+///
+/// Tabulates `p(f(x0, x1, ...))` grouped by `p(x0), p(x1), ...`.
+/// Arguments where `f` or `p` is not defined are skipped.
+pub fn synthesize(program: &Program, f: usize, p: usize) -> Result<Synthesis, String> {
+    let mut cases: Vec<(Vec<Term>, Term)> = vec![];
+    // The arguments that produced each case.
+    let mut case_args: Vec<Vec<Term>> = vec![];
+    for args in program.arguments(f)? {
+        let ret = match program.call(f, &args)
+            .and_then(|val| program.call(p, &[val])) {
+            Ok(val) => val,
+            Err(_) => continue,
+        };
+        let p_args: Result<Vec<Term>, String> = args.iter()
+            .map(|arg| program.call(p, ::std::slice::from_ref(arg))).collect();
+        let p_args = match p_args {
+            Ok(p_args) => p_args,
+            Err(_) => continue,
+        };
+        match cases.iter().position(|c| c.0 == p_args) {
+            Some(i) if cases[i].1 != ret => {
+                return Ok(Synthesis::Conflict(Conflict {
+                    a: case_args[i].clone(),
+                    b: args,
+                }));
+            }
+            Some(_) => {}
+            None => {
+                cases.push((p_args, ret));
+                case_args.push(args);
+            }
+        }
+    }
+    Ok(Synthesis::Table(Table {f, p, cases}))
+}
+
+/// Generates source for a truth table, e.g.:
+///
+/// ```text
+/// fn and [not] (bool, bool) -> bool;
+/// [:] (false, false) -> false;
+/// [:] (_, _) -> true;
+/// ```
+///
+/// When the table covers every combination of arguments,
+/// the most frequent value is returned by a last case with `_` arguments.
+pub fn table_to_source(program: &Program, table: &Table) -> Result<String, String> {
+    let name = |f: usize| program.name(f)
+        .ok_or_else(|| format!("Could not find name of function `{}`", f));
+    let n = match program.signature(table.f) {
+        None => { return Err("Expected function with type signature".into()); }
+        Some((args, _)) => args.len(),
+    };
+    let ty = match program.signature(table.p) {
+        None => { return Err("Expected path with type signature".into()); }
+        Some((_, ty)) => name(ty)?,
+    };
+
+    // Count the values of the path for each argument.
+    let mut values: Vec<Vec<&Term>> = vec![vec![]; n];
+    for (args, _) in &table.cases {
+        for (k, arg) in args.iter().enumerate() {
+            if !values[k].contains(&arg) {values[k].push(arg)};
+        }
+    }
+    let complete = n > 0 &&
+        values.iter().map(|v| v.len()).product::<usize>() == table.cases.len();
+
+    // Find the most frequent value.
+    let mut default: Option<(&Term, usize)> = None;
+    if complete {
+        for (_, ret) in &table.cases {
+            let count = table.cases.iter().filter(|c| &c.1 == ret).count();
+            if default.map(|d| count > d.1).unwrap_or(true) {
+                default = Some((ret, count));
+            }
+        }
+    }
+
+    let mut res = format!("fn {} [{}] ({}) -> {};\n",
+        name(table.f)?, name(table.p)?, vec![ty; n].join(", "), ty);
+    for (args, ret) in &table.cases {
+        if default.map(|d| d.0 == ret).unwrap_or(false) {continue};
+        let args: Vec<String> = args.iter().map(|arg| format!("{}", arg)).collect();
+        res.push_str(&format!("[:] ({}) -> {};\n", args.join(", "), ret));
+    }
+    if let Some((ret, _)) = default {
+        res.push_str(&format!("[:] ({}) -> {};\n", vec!["_"; n].join(", "), ret));
+    }
+    Ok(res)
+}
+
+/// Gets the name of a generated variable.
+pub fn var_name(i: usize) -> String {
+    match i {
+        0 => "X".into(),
+        1 => "Y".into(),
+        2 => "Z".into(),
+        _ => format!("X{}", i),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOOL: &str = "
+fn type() -> type;
+bool: type;
+true: bool;
+false: bool;
+fn and(bool, bool) -> bool;
+[:] (true, true) -> true;
+[:] (_, _) -> false;
+fn or(bool, bool) -> bool;
+[:] (false, false) -> false;
+[:] (_, _) -> true;
+fn not(bool) -> bool;
+[:] (true) -> false;
+[:] (false) -> true;
+    ";
+
+    #[test]
+    fn and_not() {
+        let program = Program::from_source(BOOL).unwrap();
+        let and = program.find("and").unwrap();
+        let or = program.find("or").unwrap();
+        let not = program.find("not").unwrap();
+        let sym_paths = find(&program, and, &[not]).unwrap();
+        assert_eq!(sym_paths, vec![SymPath {f: and, p: not, g: or}]);
+        assert_eq!(to_source(&program, &sym_paths[0]).unwrap(),
+            "fn and [not] (bool, bool) -> bool;\n[:] (X, Y) -> or(X, Y);\n");
+
+        assert_eq!(find(&program, or, &[not]).unwrap(),
+                   vec![SymPath {f: or, p: not, g: and}]);
+        assert_eq!(find(&program, not, &[not]).unwrap(),
+                   vec![SymPath {f: not, p: not, g: not}]);
+    }
+
+    #[test]
+    fn find_by_signature() {
+        let mut program = Program::from_source(BOOL).unwrap();
+        program.load("
+fn id(bool) -> bool;
+[:] (X) -> X;
+num: type;
+fn num_id(num) -> num;
+[:] (X) -> X;
+        ").unwrap();
+        let id = program.find("id").unwrap();
+        let not = program.find("not").unwrap();
+        // `num_id` behaves like `id` on `bool`, but takes `num`.
+        assert_eq!(find(&program, id, &[not]).unwrap(),
+                   vec![SymPath {f: id, p: not, g: id}]);
+    }
+
+    #[test]
+    fn synthesize_and_not() {
+        let program = Program::from_source(BOOL).unwrap();
+        let and = program.find("and").unwrap();
+        let not = program.find("not").unwrap();
+        let table = match synthesize(&program, and, not).unwrap() {
+            Synthesis::Table(table) => table,
+            x => panic!("Expected table, found `{:?}`", x),
+        };
+        assert_eq!(table.cases.len(), 4);
+        assert_eq!(table_to_source(&program, &table).unwrap(), "\
+fn and [not] (bool, bool) -> bool;
+[:] (false, false) -> false;
+[:] (_, _) -> true;
+");
+    }
+
+    #[test]
+    fn declared_paths() {
+        let mut program = Program::from_source(BOOL).unwrap();
+        let and = program.find("and").unwrap();
+        let not = program.find("not").unwrap();
+        let table = match synthesize(&program, and, not).unwrap() {
+            Synthesis::Table(table) => table,
+            x => panic!("Expected table, found `{:?}`", x),
+        };
+        program.load(&table_to_source(&program, &table).unwrap()).unwrap();
+        program.load("
+fn id(bool) -> bool;
+[:] (X) -> X;
+fn not [id -> not] (bool) -> bool;
+[:] (X) -> X;
+fn and [not x not -> id] (bool, bool) -> bool;
+[:] (_, _) -> true;
+        ").unwrap();
+        let id = program.find("id").unwrap();
+        let and_not = program.find("and[not]").unwrap();
+        let not_id_not = program.find("not[id -> not]").unwrap();
+        let and_not_not_id = program.find("and[not x not -> id]").unwrap();
+        assert_eq!(check_declared(&program).unwrap(), vec![
+            (AsymPath {f: and, ps: vec![not, not], q: not, g: and_not}, true),
+            (AsymPath {f: not, ps: vec![id], q: not, g: not_id_not}, true),
+            (AsymPath {f: and, ps: vec![not, not], q: id, g: and_not_not_id}, false),
+        ]);
+    }
+
+    #[test]
+    fn synthesize_conflict() {
+        let program = Program::from_source("
+fn type() -> type;
+bool: type;
+true: bool;
+false: bool;
+num: type;
+zero: num;
+one: num;
+two: num;
+fn inc(num) -> num;
+[:] (zero) -> one;
+[:] (one) -> two;
+[:] (two) -> zero;
+fn is_zero(num) -> bool;
+[:] (zero) -> true;
+[:] (_) -> false;
+        ").unwrap();
+        let inc = program.find("inc").unwrap();
+        let is_zero = program.find("is_zero").unwrap();
+        // `is_zero(one) == is_zero(two)`, but `is_zero(two) != is_zero(zero)`.
+        assert_eq!(synthesize(&program, inc, is_zero).unwrap(),
+            Synthesis::Conflict(Conflict {
+                a: vec![Term::sym("one")],
+                b: vec![Term::sym("two")],
+            }));
+    }
+}
@@ -0,0 +1,226 @@
+//! Terms of expressions and values.
+
+use piston_meta::*;
+use range::Range;
+use std::fmt;
+use std::sync::Arc;
+
+use interpreter::Op;
+use program::Program;
+
+/// A term, e.g. `not([false] false)`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Term {
+    /// A function name applied to arguments, e.g. `not(false)`.
+    ///
+    /// A name without arguments is a symbol, e.g. `false`.
+    App(Arc<String>, Vec<Term>),
+    /// A path, e.g. `[false] false`.
+    Path(Arc<String>, Box<Term>),
+}
+
+impl Term {
+    /// Creates a symbol.
+    pub fn sym(name: &str) -> Term {
+        Term::App(Arc::new(name.into()), vec![])
+    }
+
+    /// Parses an expression, e.g. `not(not(false))`.
+    pub fn parse(source: &str) -> Result<Term, String> {
+        let rules = ::shared_expression_rules();
+        let mut data = vec![];
+        if let Err(err) = parse(rules, source, &mut data) {
+            let mut w: Vec<u8> = vec![];
+            ParseErrorHandler::new(source).write(&mut w, err)
+                .map_err(|err| format!("{}", err))?;
+            return Err(String::from_utf8_lossy(&w).into_owned());
+        }
+        match read_term("arg", &data, 0, &mut vec![]) {
+            Ok((range, term)) if range.length == data.len() => Ok(term),
+            _ => Err("Could not convert meta data".into()),
+        }
+    }
+
+    /// Lowers to instructions in the order they are written,
+    /// e.g. `not([false] false)` is `Call, FnRef(not), Path, FnRef(false), FnRef(false)`.
+    pub fn to_ops(&self, program: &Program) -> Result<Vec<Op>, String> {
+        let mut ops = vec![];
+        self.push_ops(program, &mut ops)?;
+        Ok(ops)
+    }
+
+    fn push_ops(&self, program: &Program, ops: &mut Vec<Op>) -> Result<(), String> {
+        let find = |name: &str| program.find(name)
+            .ok_or_else(|| format!("Could not find `{}`", name));
+        match *self {
+            Term::App(ref name, ref args) => {
+                if !args.is_empty() {ops.push(Op::Call)};
+                ops.push(Op::FnRef(find(name)?));
+                for arg in args {
+                    arg.push_ops(program, ops)?;
+                }
+            }
+            Term::Path(ref p, ref term) => {
+                ops.push(Op::Path);
+                ops.push(Op::FnRef(find(p)?));
+                term.push_ops(program, ops)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Encodes a value as a stack segment,
+    /// e.g. `[false] false` is `[FnRef(false), FnRef(false), Path]`.
+    ///
+    /// Returns an error if the term contains a function application.
+    pub fn to_stack(&self, program: &Program) -> Result<Vec<Op>, String> {
+        if let Term::App(ref name, ref args) = *self.value() {
+            if !args.is_empty() {
+                return Err(format!("Expected value, found application of `{}`", name));
+            }
+        }
+        let mut st = self.to_ops(program)?;
+        st.reverse();
+        Ok(st)
+    }
+
+    /// Returns the term without paths, e.g. `false` for `[not] false`.
+    pub fn value(&self) -> &Term {
+        match *self {
+            Term::App(..) => self,
+            Term::Path(_, ref term) => term.value(),
+        }
+    }
+
+    /// Decodes a value from a stack segment,
+    /// e.g. `[FnRef(false), FnRef(false), Path]` is `[false] false`.
+    pub fn from_stack(program: &Program, st: &[Op]) -> Result<Term, String> {
+        let name = |f: usize| program.name(f).map(|name| Arc::new(name.to_string()))
+            .ok_or_else(|| format!("Could not find name of function `{}`", f));
+        match st.split_last() {
+            Some((&Op::Path, rest)) => match rest.split_last() {
+                Some((&Op::FnRef(p), rest)) => {
+                    Ok(Term::Path(name(p)?, Box::new(Term::from_stack(program, rest)?)))
+                }
+                x => Err(format!("Expected `FnRef` after `Path`, found `{:?}`", x.map(|x| x.0))),
+            },
+            Some((&Op::FnRef(f), &[])) => Ok(Term::App(name(f)?, vec![])),
+            Some((x, &[])) => Err(format!("Expected value, found `{:?}`", x)),
+            Some(_) => Err("Expected a single value on the stack".into()),
+            None => Err("Expected value, found empty stack".into()),
+        }
+    }
+}
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Term::App(ref name, ref args) => {
+                write!(f, "{}", name)?;
+                if !args.is_empty() {
+                    write!(f, "(")?;
+                    for (i, arg) in args.iter().enumerate() {
+                        if i > 0 {write!(f, ", ")?};
+                        write!(f, "{}", arg)?;
+                    }
+                    write!(f, ")")?;
+                }
+                Ok(())
+            }
+            Term::Path(ref p, ref term) => write!(f, "[{}] {}", p, term),
+        }
+    }
+}
+
+/// Reads a term from meta data.
+fn read_term(
+    node: &str,
+    mut data: &[Range<MetaData>],
+    mut offset: usize,
+    ignored: &mut Vec<Range>
+) -> Result<(Range, Term), ()> {
+    use piston_meta::bootstrap::{ end_node, ignore, start_node, update, meta_string };
+
+    let start_offset = offset;
+    let range = start_node(node, data, offset)?;
+    update(range, &mut data, &mut offset);
+    let mut paths = vec![];
+    let mut name: Option<Arc<String>> = None;
+    let mut args = vec![];
+    loop {
+        if let Ok(range) = end_node(node, data, offset) {
+            update(range, &mut data, &mut offset);
+            break;
+        } else if let Ok((range, val)) = meta_string("path", data, offset) {
+            update(range, &mut data, &mut offset);
+            paths.push(val);
+        } else if let Ok((range, val)) = meta_string("ns_name", data, offset) {
+            update(range, &mut data, &mut offset);
+            name = Some(val);
+        } else if let Ok(range) = start_node("repeated_arguments", data, offset) {
+            update(range, &mut data, &mut offset);
+            loop {
+                if let Ok(range) = end_node("repeated_arguments", data, offset) {
+                    update(range, &mut data, &mut offset);
+                    break;
+                } else if let Ok((range, arg)) = read_term("arg", data, offset, ignored) {
+                    update(range, &mut data, &mut offset);
+                    args.push(arg);
+                } else {
+                    let range = ignore(data, offset);
+                    update(range, &mut data, &mut offset);
+                    ignored.push(range);
+                }
+            }
+        } else {
+            let range = ignore(data, offset);
+            update(range, &mut data, &mut offset);
+            ignored.push(range);
+        }
+    }
+
+    let mut term = Term::App(name.ok_or(())?, args);
+    for p in paths.into_iter().rev() {
+        term = Term::Path(p, Box::new(term));
+    }
+    Ok((Range::new(start_offset, offset - start_offset), term))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_term() {
+        assert_eq!(Term::parse("[not] not(false, [true] X)").unwrap(),
+            Term::Path(Arc::new("not".into()), Box::new(Term::App(
+                Arc::new("not".into()), vec![
+                    Term::sym("false"),
+                    Term::Path(Arc::new("true".into()), Box::new(Term::sym("X")))
+                ]
+            ))));
+        assert!(Term::parse("not(").is_err());
+    }
+
+    #[test]
+    fn display() {
+        for source in &["false", "[false] false", "[not] [id] x", "and(true, [not] f(x))"] {
+            assert_eq!(format!("{}", Term::parse(source).unwrap()), *source);
+        }
+    }
+
+    #[test]
+    fn stack() {
+        let program = Program::from_source("
+fn bool() -> bool;
+true: bool;
+false: bool;
+        ").unwrap();
+        let false_ = program.find("false").unwrap();
+        let term = Term::parse("[false] false").unwrap();
+        let st = term.to_stack(&program).unwrap();
+        assert_eq!(st, vec![Op::FnRef(false_), Op::FnRef(false_), Op::Path]);
+        assert_eq!(Term::from_stack(&program, &st).unwrap(), term);
+        assert!(Term::parse("bool(true)").unwrap().to_stack(&program).is_err());
+    }
+}